@@ -0,0 +1,422 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream, Parser, Result};
+use syn::{meta, parse_quote, Attribute, Error, Ident, LitInt, LitStr, Path, Token, Type};
+
+/// Arguments of `#[crdt(..)]` on a struct, given either to the attribute macro
+/// or as a helper attribute next to `#[derive(CRDT)]`.
+///
+/// The actor type comes first and is followed by the options:
+/// `#[crdt(u64, crate = "my_facade::crdts", no_default, extra_derives(Hash))]`.
+#[derive(Default)]
+pub(crate) struct Args {
+    pub(crate) actor: Option<Type>,
+    crdts_macro: Option<Path>,
+    crdts: Option<Path>,
+    serde: Option<Path>,
+    /// Leave `Default` out of the derives added to the struct.
+    no_default: bool,
+    /// Leave `Debug` out of the derives added to the struct.
+    no_debug: bool,
+    /// Derive neither `Serialize` nor `Deserialize`, on the struct or the op.
+    pub(crate) no_serde: bool,
+    /// Derived on the struct next to the default ones.
+    extra_derives: Vec<Path>,
+    /// Derived on the generated `CrdtOp` next to the default ones.
+    op_extra_derives: Vec<Path>,
+    /// Part of another `#[crdt]` struct: no `v_clock` and no `dot` in ops.
+    pub(crate) embedded: bool,
+    /// Track the dots touching each field in `field_clocks` and generate
+    /// `delta_since`.
+    pub(crate) delta: bool,
+    /// `unknown_ops = "reject"`: fail to deserialize ops carrying field ops
+    /// this version does not know, instead of dropping them. Unset it is
+    /// `"ignore"`, or `"reject"` with `op = "enum"`.
+    reject_unknown_ops: Option<bool>,
+    /// Schema version, kept in a `version` field of the struct and its ops.
+    pub(crate) version: Option<LitInt>,
+    /// The struct states of the previous version are read as.
+    pub(crate) migrate_from: Option<Type>,
+    /// Upgrades a `migrate_from` state.
+    pub(crate) with: Option<Path>,
+    /// Upgrades an op of `migrate_from`.
+    pub(crate) op_with: Option<Path>,
+    /// Generate `DataView` and `Data::view`.
+    pub(crate) view: bool,
+    /// Generate `DataReadCtx` and `Data::read_ctx`.
+    pub(crate) read_ctx: bool,
+    /// Generate `DataIntent` and `Data::intent_to_op`.
+    pub(crate) intents: bool,
+    /// `op = "enum"`: ops carry a list of `DataFieldOp`s instead of an
+    /// `Option` per field. Implies `unknown_ops = "reject"`.
+    pub(crate) enum_ops: bool,
+}
+
+const OPTIONS: &[&str] = &[
+    "crdts_macro",
+    "crate",
+    "serde",
+    "no_default",
+    "no_debug",
+    "no_serde",
+    "extra_derives",
+    "op_extra_derives",
+    "embedded",
+    "delta",
+    "unknown_ops",
+    "version",
+    "migrate_from",
+    "with",
+    "op_with",
+    "view",
+    "read_ctx",
+    "intents",
+    "op",
+];
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut args = Args::default();
+        let starts_with_option = !input.peek2(Token![::])
+            && input
+                .fork()
+                .call(Ident::parse_any)
+                .is_ok_and(|i| OPTIONS.iter().any(|o| i == o));
+        if !input.is_empty() && !starts_with_option {
+            args.actor = Some(input.parse().map_err(|e| {
+                Error::new(e.span(), "expected the actor type, e.g. `#[crdt(u64)]`")
+            })?);
+            if input.is_empty() {
+                return Ok(args);
+            }
+            input.parse::<Token![,]>()?;
+        }
+        let options: TokenStream = input.parse()?;
+        meta::parser(|meta| {
+            if meta.path.is_ident("crdts_macro") {
+                args.crdts_macro = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("crate") {
+                args.crdts = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("serde") {
+                args.serde = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("no_default") {
+                args.no_default = true;
+            } else if meta.path.is_ident("no_debug") {
+                args.no_debug = true;
+            } else if meta.path.is_ident("embedded") {
+                args.embedded = true;
+            } else if meta.path.is_ident("delta") {
+                args.delta = true;
+            } else if meta.path.is_ident("view") {
+                args.view = true;
+            } else if meta.path.is_ident("read_ctx") {
+                args.read_ctx = true;
+            } else if meta.path.is_ident("intents") {
+                args.intents = true;
+            } else if meta.path.is_ident("unknown_ops") {
+                let policy = meta.value()?.parse::<LitStr>()?;
+                args.reject_unknown_ops = match policy.value().as_str() {
+                    "ignore" => Some(false),
+                    "reject" => Some(true),
+                    _ => {
+                        return Err(Error::new_spanned(
+                            policy,
+                            "expected `\"ignore\"` or `\"reject\"`",
+                        ))
+                    }
+                };
+            } else if meta.path.is_ident("op") {
+                let shape = meta.value()?.parse::<LitStr>()?;
+                args.enum_ops = match shape.value().as_str() {
+                    "struct" => false,
+                    "enum" => true,
+                    _ => {
+                        return Err(Error::new_spanned(
+                            shape,
+                            "expected `\"struct\"` or `\"enum\"`",
+                        ))
+                    }
+                };
+            } else if meta.path.is_ident("version") {
+                let version = meta.value()?.parse::<LitInt>()?;
+                version.base10_parse::<u32>()?;
+                args.version = Some(version);
+            } else if meta.path.is_ident("migrate_from") {
+                args.migrate_from = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("with") {
+                args.with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("op_with") {
+                args.op_with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("no_serde") {
+                args.no_serde = true;
+            } else if meta.path.is_ident("extra_derives") {
+                meta.parse_nested_meta(|derive| {
+                    args.extra_derives.push(derive.path);
+                    Ok(())
+                })?;
+            } else if meta.path.is_ident("op_extra_derives") {
+                meta.parse_nested_meta(|derive| {
+                    args.op_extra_derives.push(derive.path);
+                    Ok(())
+                })?;
+            } else {
+                return Err(meta.error(format!(
+                    "unknown option, expected one of `{}`",
+                    OPTIONS.join("`, `")
+                )));
+            }
+            Ok(())
+        })
+        .parse2(options)?;
+        Ok(args)
+    }
+}
+
+impl Args {
+    /// Arguments of the `crdt` attribute macro, which needs the actor type.
+    pub(crate) fn parse_attr(tokens: proc_macro::TokenStream) -> Result<Self> {
+        let args: Args = syn::parse(tokens)?;
+        if args.actor.is_none() {
+            return Err(Error::new(
+                Span::call_site(),
+                "expected the actor type, e.g. `#[crdt(u64)]`",
+            ));
+        }
+        args.check()?;
+        Ok(args)
+    }
+
+    /// Arguments collected from the `#[crdt(..)]` helper attributes of a
+    /// `#[derive(CRDT)]` struct.
+    pub(crate) fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+        let mut args = Args::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("crdt")) {
+            let other: Args = attr.parse_args()?;
+            if other.no_default || other.no_debug || !other.extra_derives.is_empty() {
+                return Err(Error::new_spanned(
+                    attr,
+                    "`no_default`, `no_debug` and `extra_derives` only apply to `#[crdt(..)]` on its own, list the derives directly instead",
+                ));
+            }
+            args.actor = other.actor.or(args.actor);
+            args.crdts_macro = other.crdts_macro.or(args.crdts_macro);
+            args.crdts = other.crdts.or(args.crdts);
+            args.serde = other.serde.or(args.serde);
+            args.no_serde |= other.no_serde;
+            args.embedded |= other.embedded;
+            args.delta |= other.delta;
+            args.view |= other.view;
+            args.read_ctx |= other.read_ctx;
+            args.intents |= other.intents;
+            args.enum_ops |= other.enum_ops;
+            args.reject_unknown_ops = other.reject_unknown_ops.or(args.reject_unknown_ops);
+            args.version = other.version.or(args.version);
+            args.migrate_from = other.migrate_from.or(args.migrate_from);
+            args.with = other.with.or(args.with);
+            args.op_with = other.op_with.or(args.op_with);
+            args.op_extra_derives.extend(other.op_extra_derives);
+        }
+        args.check()?;
+        Ok(args)
+    }
+
+    /// Reject options that only make sense together with others.
+    fn check(&self) -> Result<()> {
+        let error = |msg| Err(Error::new(Span::call_site(), msg));
+        if self.migrate_from.is_some() != self.with.is_some() {
+            return error("`migrate_from = \"..\"` and `with = \"..\"` have to be given together");
+        }
+        if self.migrate_from.is_some() && self.version.is_none() {
+            return error("`migrate_from` needs the `version = N` of this struct");
+        }
+        if self.migrate_from.is_some() && self.no_serde {
+            return error(
+                "`migrate_from` reads serialized states and cannot be combined with `no_serde`",
+            );
+        }
+        if self.op_with.is_some() && self.migrate_from.is_none() {
+            return error("`op_with` needs `migrate_from`");
+        }
+        if self.enum_ops && self.reject_unknown_ops == Some(false) {
+            return error(
+                "`op = \"enum\"` cannot skip field ops it does not know, remove `unknown_ops = \"ignore\"`",
+            );
+        }
+        if self.version.is_some() && self.embedded {
+            return error(
+                "`version` only applies to the root struct and cannot be combined with `embedded`",
+            );
+        }
+        Ok(())
+    }
+
+    /// Fields that are maintained by the generated code rather than being
+    /// CRDTs of their own.
+    pub(crate) fn is_bookkeeping(&self, field: &str) -> bool {
+        field == "field_clocks" && self.delta || field == "version" && self.version.is_some()
+    }
+
+    /// Path of this crate, `crdts_macro` unless `crdts_macro = ".."` is given.
+    pub(crate) fn crdts_macro(&self) -> Path {
+        self.crdts_macro
+            .clone()
+            .unwrap_or_else(|| parse_quote!(crdts_macro))
+    }
+
+    /// Path of the `crdts` crate, re-exported by `crdts_macro` unless
+    /// `crate = ".."` is given.
+    pub(crate) fn crdts(&self) -> Path {
+        self.crdts.clone().unwrap_or_else(|| {
+            let crdts_macro = self.crdts_macro();
+            parse_quote!(#crdts_macro::crdts)
+        })
+    }
+
+    /// Path of the `serde` crate, re-exported by `crdts_macro` unless
+    /// `serde = ".."` is given.
+    pub(crate) fn serde(&self) -> Path {
+        self.serde.clone().unwrap_or_else(|| {
+            let crdts_macro = self.crdts_macro();
+            parse_quote!(#crdts_macro::serde)
+        })
+    }
+
+    /// Derive attributes the attribute macro puts on the struct.
+    pub(crate) fn derives(&self) -> TokenStream {
+        let mut derives: Vec<Path> = Vec::new();
+        if !self.no_default {
+            derives.push(parse_quote!(Default));
+        }
+        if !self.no_debug {
+            derives.push(parse_quote!(std::fmt::Debug));
+        }
+        derives.extend([
+            parse_quote!(Clone),
+            parse_quote!(PartialEq),
+            parse_quote!(Eq),
+        ]);
+        self.with_serde(derives, &self.extra_derives)
+    }
+
+    /// Derive attributes of the generated `CrdtOp`.
+    pub(crate) fn op_derives(&self) -> TokenStream {
+        let mut tokens = self.field_op_derives();
+        if self.reject_unknown_ops.unwrap_or(self.enum_ops) && !self.no_serde {
+            tokens.extend(quote!(#[serde(deny_unknown_fields)]));
+        }
+        tokens
+    }
+
+    /// Derive attributes of the generated `FieldOp` of `op = "enum"`, which
+    /// rejects unknown field ops, hence the implied `unknown_ops = "reject"`.
+    pub(crate) fn field_op_derives(&self) -> TokenStream {
+        let derives = vec![
+            parse_quote!(std::fmt::Debug),
+            parse_quote!(Clone),
+            parse_quote!(PartialEq),
+            parse_quote!(Eq),
+        ];
+        self.with_serde(derives, &self.op_extra_derives)
+    }
+
+    /// Derive attributes of the generated `ReadCtx`, `crdts::ctx::ReadCtx` is
+    /// not `Clone`.
+    pub(crate) fn read_ctx_derives(&self) -> TokenStream {
+        let derives = vec![
+            parse_quote!(std::fmt::Debug),
+            parse_quote!(PartialEq),
+            parse_quote!(Eq),
+        ];
+        self.with_serde(derives, &[])
+    }
+
+    /// Derive attributes of the generated `Intent`.
+    pub(crate) fn intent_derives(&self) -> TokenStream {
+        let derives = vec![
+            parse_quote!(std::fmt::Debug),
+            parse_quote!(Clone),
+            parse_quote!(PartialEq),
+        ];
+        self.with_serde(derives, &[])
+    }
+
+    /// Derive attributes of the generated `View`, which is only ever
+    /// serialized.
+    pub(crate) fn view_derives(&self) -> TokenStream {
+        let mut derives: Vec<Path> = vec![
+            parse_quote!(std::fmt::Debug),
+            parse_quote!(Clone),
+            parse_quote!(PartialEq),
+        ];
+        if self.no_serde {
+            return quote!(#[derive(#(#derives),*)]);
+        }
+        let serde = self.serde();
+        let serde_crate = serde.to_token_stream().to_string();
+        derives.push(parse_quote!(#serde::Serialize));
+        quote! {
+            #[derive(#(#derives),*)]
+            #[serde(crate = #serde_crate)]
+        }
+    }
+
+    /// `#[serde(default)]`, put on fields that older versions may not have
+    /// written.
+    pub(crate) fn serde_default(&self) -> TokenStream {
+        if self.no_serde {
+            TokenStream::new()
+        } else {
+            quote!(#[serde(default)])
+        }
+    }
+
+    /// serde attributes of the `Option` field op slots of `CrdtOp`: missing
+    /// ones deserialize as `None` and `None` is not written.
+    pub(crate) fn op_slot_serde(&self) -> TokenStream {
+        if self.no_serde {
+            TokenStream::new()
+        } else {
+            quote!(#[serde(default, skip_serializing_if = "Option::is_none")])
+        }
+    }
+
+    /// Serialize the op of a field with `#[crdt(tag = N)]` as `"N"`.
+    pub(crate) fn op_tag(&self, tag: &LitInt) -> TokenStream {
+        if self.no_serde {
+            TokenStream::new()
+        } else {
+            let tag = tag.base10_digits();
+            quote!(#[serde(rename = #tag)])
+        }
+    }
+
+    /// Derive attributes of the generated `Delta`.
+    pub(crate) fn delta_derives(&self) -> TokenStream {
+        let derives = vec![
+            parse_quote!(std::fmt::Debug),
+            parse_quote!(Clone),
+            parse_quote!(PartialEq),
+            parse_quote!(Eq),
+        ];
+        self.with_serde(derives, &[])
+    }
+
+    fn with_serde(&self, mut derives: Vec<Path>, extra: &[Path]) -> TokenStream {
+        if self.no_serde {
+            derives.extend_from_slice(extra);
+            return quote!(#[derive(#(#derives),*)]);
+        }
+        let serde = self.serde();
+        let serde_crate = serde.to_token_stream().to_string();
+        derives.extend([
+            parse_quote!(#serde::Serialize),
+            parse_quote!(#serde::Deserialize),
+        ]);
+        derives.extend_from_slice(extra);
+        quote! {
+            #[derive(#(#derives),*)]
+            #[serde(crate = #serde_crate)]
+        }
+    }
+}
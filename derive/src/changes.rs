@@ -0,0 +1,109 @@
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::{parse_quote, Generics, Type, WherePredicate};
+
+use crate::args::Args;
+use crate::field_ident;
+
+/// `DataChanges` with a flag per field, returned by `apply_with_changes` and
+/// `merge_with_changes`. An applied op changes the fields it has an op for, a
+/// merge the fields that differ afterwards. Fields that are not `PartialEq`
+/// and `Clone` only make the methods unusable, hence the higher-ranked bounds.
+pub(crate) fn impl_changes(
+    name: &Ident,
+    generics: &Generics,
+    fields: &[(String, Type)],
+    args: &Args,
+) -> TokenStream {
+    let crdts = args.crdts();
+    let changes_name = Ident::new(&(name.to_string() + "Changes"), Span::call_site());
+    let op_name = Ident::new(&(name.to_string() + "CrdtOp"), Span::call_site());
+    let field_op = Ident::new(&(name.to_string() + "FieldOp"), Span::call_site());
+
+    let fields = fields
+        .iter()
+        .filter(|(f, _)| f != "v_clock")
+        .map(|(f, ty)| {
+            (
+                field_ident(f),
+                Ident::new(&format!("{f}_op"), Span::call_site()),
+                ty,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut generics = generics.clone();
+    generics.make_where_clause().predicates.extend(
+        fields.iter().map(|(_, _, ty)| -> WherePredicate {
+            parse_quote!(for<'__crdt> #ty: PartialEq + Clone)
+        }),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let flags = fields.iter().map(|(field, _, _)| {
+        let doc = format!("`{field}` has changed.");
+        quote! {
+            #[doc = #doc]
+            pub #field: bool,
+        }
+    });
+    let is_empty = fields.iter().map(|(field, _, _)| quote!(!self.#field));
+    let touched = fields.iter().map(|(field, op, _)| {
+        if args.enum_ops {
+            let variant = Ident::new(
+                &field.unraw().to_string().to_case(Case::Pascal),
+                Span::call_site(),
+            );
+            quote!(#field: op.ops.iter().any(|op| matches!(op, #field_op::#variant(_))),)
+        } else {
+            quote!(#field: op.#op.is_some(),)
+        }
+    });
+    let before = fields.iter().map(|(field, _, _)| {
+        quote! {
+            let #field = (self.#field != other.#field).then(|| self.#field.clone());
+        }
+    });
+    let compare = fields
+        .iter()
+        .map(|(field, _, _)| quote!(#field: #field.is_some_and(|before| before != self.#field),));
+
+    let changes_doc = format!("The fields of a [`{name}`] changed by an op or a merge.");
+    quote! {
+        #[doc = #changes_doc]
+        #[derive(std::fmt::Debug, Default, Clone, Copy, PartialEq, Eq)]
+        pub struct #changes_name {
+            #(#flags)*
+        }
+
+        impl #changes_name {
+            /// Returns `true` if no field has changed.
+            pub fn is_empty(&self) -> bool {
+                true #(&& #is_empty)*
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Apply `op` like `CmRDT::apply`, reporting the fields it changed.
+            pub fn apply_with_changes(&mut self, op: #op_name #ty_generics) -> #changes_name {
+                let changes = if self.v_clock.get(&op.dot.actor) < op.dot.counter {
+                    #changes_name { #(#touched)* }
+                } else {
+                    #changes_name::default()
+                };
+                #crdts::CmRDT::apply(self, op);
+                changes
+            }
+
+            /// Merge `other` like `CvRDT::merge`, reporting the fields it
+            /// changed. Only fields that differ are cloned to compare them.
+            pub fn merge_with_changes(&mut self, other: Self) -> #changes_name {
+                #(#before)*
+                #crdts::CvRDT::merge(self, other);
+                #changes_name { #(#compare)* }
+            }
+        }
+    }
+}
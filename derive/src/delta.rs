@@ -0,0 +1,142 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Generics, Type};
+
+use crate::args::Args;
+use crate::field_ident;
+
+/// `DataDelta` and the `DeltaCrdt` impl of a `delta` struct. A field is part
+/// of the delta as a whole as soon as one of the dots in its `field_clocks`
+/// entry is unknown to the peer.
+pub(crate) fn impl_delta(
+    name: &Ident,
+    generics: &Generics,
+    fields: &[(String, Type)],
+    actor: &Type,
+    args: &Args,
+) -> TokenStream {
+    let crdts_macro = args.crdts_macro();
+    let crdts = args.crdts();
+    let delta_name = Ident::new(&(name.to_string() + "Delta"), Span::call_site());
+    let delta_derives = args.delta_derives();
+
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+    let mut impl_generics = generics.clone();
+    impl_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(#actor: #crdts::Actor + std::fmt::Debug));
+    let (delta_impl_generics, _, delta_where_clause) = impl_generics.split_for_impl();
+
+    let fields = fields
+        .iter()
+        .filter(|(f, _)| f != "v_clock")
+        .map(|(f, ty)| (f, field_ident(f), ty))
+        .collect::<Vec<_>>();
+    let slots = fields
+        .iter()
+        .map(|(_, field, ty)| quote!(pub #field: Option<#ty>,));
+    let nones = fields.iter().map(|(_, field, _)| quote!(#field: None,));
+    let is_none = fields
+        .iter()
+        .map(|(_, field, _)| quote!(self.#field.is_none()));
+    let collect = fields.iter().map(|(f, field, _)| {
+        quote! {
+            if let Some(field_clock) = self.field_clocks.get(#f) {
+                if self.field_clocks.changed_since(#f, clock) {
+                    delta.#field = Some(self.#field.clone());
+                    delta.field_clocks.merge_field(#f, field_clock.clone());
+                }
+            }
+        }
+    });
+    let merge = fields.iter().map(|(_, field, _)| {
+        quote! {
+            if let Some(#field) = delta.#field {
+                #crdts::CvRDT::merge(&mut self.#field, #field);
+            }
+        }
+    });
+
+    let delta_doc =
+        format!("The fields of a [`{name}`] another replica has not seen, see `delta_since`.");
+    quote! {
+        #[doc = #delta_doc]
+        #[allow(clippy::type_complexity)]
+        #delta_derives
+        pub struct #delta_name #generics #where_clause {
+            pub v_clock: #crdts::VClock<#actor>,
+            pub field_clocks: #crdts_macro::FieldClocks<#actor>,
+            #(#slots)*
+        }
+
+        impl #delta_impl_generics #delta_name #ty_generics #delta_where_clause {
+            /// Returns `true` if the delta carries no field.
+            pub fn is_empty(&self) -> bool {
+                true #(&& #is_none)*
+            }
+        }
+
+        impl #delta_impl_generics #crdts_macro::DeltaCrdt for #name #ty_generics #delta_where_clause {
+            type Delta = #delta_name #ty_generics;
+
+            /// Changed fields are cloned whole, not just their unseen part.
+            fn delta_since(&self, clock: &#crdts::VClock<#actor>) -> Self::Delta {
+                let mut delta = #delta_name {
+                    v_clock: self.v_clock.clone(),
+                    field_clocks: Default::default(),
+                    #(#nones)*
+                };
+                #(#collect)*
+                delta
+            }
+
+            /// The delta has to be built against this replica's clock, as
+            /// its `v_clock` is taken over as is.
+            fn merge_delta(&mut self, delta: Self::Delta) {
+                #(#merge)*
+                self.field_clocks.merge(delta.field_clocks);
+                #crdts::CvRDT::merge(&mut self.v_clock, delta.v_clock);
+            }
+        }
+    }
+}
+
+/// The `DeltaCrdt` impl of a struct without `delta`, whose delta is a clone
+/// of the whole state, so it can still be synced. Structs that are not
+/// `Clone` only make the impl unusable, hence the higher-ranked bound.
+pub(crate) fn impl_full_delta(
+    name: &Ident,
+    generics: &Generics,
+    actor: &Type,
+    args: &Args,
+) -> TokenStream {
+    let crdts_macro = args.crdts_macro();
+    let crdts = args.crdts();
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let self_ty = quote!(#name #ty_generics);
+    let mut generics = generics.clone();
+    let where_clause = generics.make_where_clause();
+    where_clause
+        .predicates
+        .push(parse_quote!(#actor: #crdts::Actor + std::fmt::Debug));
+    where_clause
+        .predicates
+        .push(parse_quote!(for<'__crdt> #self_ty: Clone));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #crdts_macro::DeltaCrdt for #name #ty_generics #where_clause {
+            type Delta = Self;
+
+            /// Without `field_clocks` the whole state is the delta.
+            fn delta_since(&self, _clock: &#crdts::VClock<#actor>) -> Self::Delta {
+                self.clone()
+            }
+
+            fn merge_delta(&mut self, delta: Self::Delta) {
+                #crdts::CvRDT::merge(self, delta);
+            }
+        }
+    }
+}
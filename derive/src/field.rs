@@ -0,0 +1,158 @@
+use convert_case::{Case, Casing};
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{Generics, Path, Type};
+
+/// The `DataField` enum naming each field, and `Data::FIELDS` describing
+/// them.
+pub(crate) fn impl_fields(
+    name: &Ident,
+    generics: &Generics,
+    fields: &[(String, Type)],
+    crdts_macro: &Path,
+) -> TokenStream {
+    let field_name = Ident::new(&(name.to_string() + "Field"), Span::call_site());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let fields = fields
+        .iter()
+        .filter(|(f, _)| f != "v_clock")
+        .map(|(f, ty)| {
+            let variant = Ident::new(&f.to_case(Case::Pascal), Span::call_site());
+            (f, variant, ty)
+        })
+        .collect::<Vec<_>>();
+
+    let variants = fields.iter().map(|(f, variant, _)| {
+        let doc = format!("`{f}`");
+        quote! {
+            #[doc = #doc]
+            #variant,
+        }
+    });
+    let all = fields.iter().map(|(_, variant, _)| variant);
+    let names = fields
+        .iter()
+        .map(|(f, variant, _)| quote!(#field_name::#variant => #f,));
+    let parse = fields
+        .iter()
+        .map(|(f, variant, _)| quote!(#f => Ok(#field_name::#variant),));
+    let infos = fields.iter().map(|(f, _, ty)| {
+        let type_name = type_name(ty);
+        let kind = kind(ty);
+        quote! {
+            #crdts_macro::FieldInfo {
+                name: #f,
+                type_name: #type_name,
+                kind: #kind,
+            },
+        }
+    });
+
+    let field_doc = format!("The fields of a [`{name}`], in declaration order.");
+    quote! {
+        #[doc = #field_doc]
+        #[derive(std::fmt::Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum #field_name {
+            #(#variants)*
+        }
+
+        impl #field_name {
+            /// Every field, in declaration order.
+            pub const ALL: &'static [#field_name] = &[#(#field_name::#all),*];
+
+            /// The field name as written in the struct.
+            pub fn name(self) -> &'static str {
+                match self {
+                    #(#names)*
+                }
+            }
+
+            const INFOS: &'static [#crdts_macro::FieldInfo] = &[#(#infos)*];
+
+            /// The static description of the field.
+            pub fn info(self) -> &'static #crdts_macro::FieldInfo {
+                &Self::INFOS[self as usize]
+            }
+        }
+
+        impl std::fmt::Display for #field_name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        impl std::str::FromStr for #field_name {
+            type Err = #crdts_macro::UnknownField;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    #(#parse)*
+                    _ => Err(#crdts_macro::UnknownField(s.to_string())),
+                }
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Name, type and CRDT kind of each field, in declaration order.
+            pub const FIELDS: &'static [#crdts_macro::FieldInfo] = #field_name::INFOS;
+        }
+    }
+}
+
+/// The type as written, without the spaces `quote` puts between tokens.
+fn type_name(ty: &Type) -> String {
+    let mut name = String::new();
+    push_tokens(&mut name, ty.to_token_stream());
+    name
+}
+
+/// Spaces are kept after `,` and `;` and where words would run together,
+/// e.g. in `<T as Trait>::X` or `&'a mut T`.
+fn push_tokens(name: &mut String, tokens: TokenStream) {
+    let after_word = |name: &String| name.ends_with(|c: char| c.is_alphanumeric() || c == '_');
+    for token in tokens {
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+                name.push_str(open);
+                push_tokens(name, group.stream());
+                name.push_str(close);
+            }
+            TokenTree::Punct(punct) => {
+                if punct.as_char() == '\'' && after_word(name) {
+                    name.push(' ');
+                }
+                name.push(punct.as_char());
+                if matches!(punct.as_char(), ',' | ';') {
+                    name.push(' ');
+                }
+            }
+            word => {
+                if after_word(name) {
+                    name.push(' ');
+                }
+                name.push_str(&word.to_string());
+            }
+        }
+    }
+}
+
+/// The last path segment of the type, e.g. `Orswot` for
+/// `crdts::Orswot<String, u64>`.
+fn kind(ty: &Type) -> String {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_default(),
+        _ => type_name(ty),
+    }
+}
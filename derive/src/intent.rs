@@ -0,0 +1,144 @@
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Generics, Type};
+
+use crate::args::Args;
+use crate::{field_ident, projection_bounds, serde_bound};
+
+/// `DataIntent` with one variant per field wrapping its `HasIntent::Intent`,
+/// the `HasIntent` impl turning it into a `DataCrdtOp` and, for root structs,
+/// `Data::intent_to_op`.
+pub(crate) fn impl_intent(
+    name: &Ident,
+    generics: &Generics,
+    fields: &[(String, Type)],
+    actor: &Type,
+    args: &Args,
+) -> TokenStream {
+    let crdts_macro = args.crdts_macro();
+    let intent_name = Ident::new(&(name.to_string() + "Intent"), Span::call_site());
+    let op_name = Ident::new(&(name.to_string() + "CrdtOp"), Span::call_site());
+    let field_op = Ident::new(&(name.to_string() + "FieldOp"), Span::call_site());
+    let intent_derives = args.intent_derives();
+
+    let fields = fields
+        .iter()
+        .filter(|(f, _)| f != "v_clock")
+        .map(|(f, ty)| {
+            (
+                field_ident(f),
+                Ident::new(&format!("{f}_op"), Span::call_site()),
+                Ident::new(&f.to_case(Case::Pascal), Span::call_site()),
+                ty,
+            )
+        })
+        .collect::<Vec<_>>();
+    let has_intent = quote!(#crdts_macro::HasIntent<#actor>);
+
+    let intents = fields
+        .iter()
+        .map(|(_, _, _, ty)| quote!(<#ty as #has_intent>::Intent))
+        .collect::<Vec<_>>();
+    let mut generics = generics.clone();
+    let predicates = &mut generics.make_where_clause().predicates;
+    for (_, _, _, ty) in &fields {
+        predicates.push(parse_quote!(#ty: #has_intent));
+    }
+    projection_bounds(
+        &mut generics,
+        &intents,
+        quote!(std::fmt::Debug + Clone + PartialEq),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let serde_bound =
+        (!args.no_serde).then(|| serde_bound(&generics, &intents, &args.serde(), true));
+    let variants = fields
+        .iter()
+        .map(|(_, _, variant, ty)| quote!(#variant(<#ty as #has_intent>::Intent),));
+
+    let body = if fields.is_empty() {
+        quote! {
+            let _ = actor;
+            match intent {}
+        }
+    } else {
+        let dot = (!args.embedded).then(|| quote!(dot: self.v_clock.inc(actor.clone()),));
+        let version = args
+            .version
+            .as_ref()
+            .map(|_| quote!(version: Default::default(),));
+        let ops = if args.enum_ops {
+            quote!(ops: Vec::new(),)
+        } else {
+            let ops = fields.iter().map(|(_, op, _, _)| op);
+            quote!(#(#ops: None,)*)
+        };
+        let arms = fields.iter().map(|(field, op, variant, ty)| {
+            let field_intent_op =
+                quote!(<#ty as #has_intent>::intent_op(&self.#field, actor, intent));
+            let set = if args.enum_ops {
+                quote!(op.ops.push(#field_op::#variant(#field_intent_op));)
+            } else {
+                quote!(op.#op = Some(#field_intent_op);)
+            };
+            quote! {
+                #intent_name::#variant(intent) => {
+                    #set
+                }
+            }
+        });
+        quote! {
+            let mut op = #op_name {
+                #dot
+                #ops
+                #version
+            };
+            match intent {
+                #(#arms)*
+            }
+            op
+        }
+    };
+    let intent_to_op = (!args.embedded).then(|| {
+        quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// Build the op carrying out `intent` under the next dot of
+                /// `actor`. The op still has to be applied.
+                pub fn intent_to_op(
+                    &self,
+                    actor: #actor,
+                    intent: #intent_name #ty_generics,
+                ) -> #op_name #ty_generics {
+                    <Self as #has_intent>::intent_op(self, actor, intent)
+                }
+            }
+        }
+    });
+
+    let intent_doc = if args.embedded {
+        format!("A change to one field of a [`{name}`], see `HasIntent`.")
+    } else {
+        format!("A change to one field of a [`{name}`], turned into an op where the state is kept, see `{name}::intent_to_op`.")
+    };
+    quote! {
+        #[doc = #intent_doc]
+        #[allow(clippy::type_complexity)]
+        #intent_derives
+        #serde_bound
+        pub enum #intent_name #generics #where_clause {
+            #(#variants)*
+        }
+
+        impl #impl_generics #has_intent for #name #ty_generics #where_clause {
+            type Intent = #intent_name #ty_generics;
+
+            fn intent_op(&self, actor: #actor, intent: Self::Intent) -> Self::Op {
+                #body
+            }
+        }
+
+        #intent_to_op
+    }
+}
@@ -1,18 +1,28 @@
+mod args;
+mod changes;
+mod delta;
+mod field;
+mod intent;
+mod observer;
+mod read_ctx;
+mod transact;
+mod version;
+mod view;
+
 use std::collections::HashMap;
 
 use convert_case::{Case, Casing};
-use proc_macro2::{Ident, Span, TokenStream};
-use quote::{quote, quote_spanned};
-use syn::parse::{Parse, ParseStream, Parser, Result};
-use syn::{parse_macro_input, Data, DataStruct, DeriveInput, Fields, Type};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parser, Result};
+use syn::{
+    parse_macro_input, parse_quote, Data, DataStruct, DeriveInput, Error, Field, Fields,
+    FieldsNamed, GenericArgument, Generics, LitInt, LitStr, Path, PathArguments, Type,
+    TypeGenerics, WherePredicate,
+};
 
-struct Args(Type);
-
-impl Parse for Args {
-    fn parse(input: ParseStream) -> Result<Self> {
-        Ok(Args(input.parse()?))
-    }
-}
+use crate::args::Args;
 
 #[proc_macro_attribute]
 pub fn crdt(
@@ -20,88 +30,501 @@ pub fn crdt(
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
     let mut ast = parse_macro_input!(input as DeriveInput);
-    let args = parse_macro_input!(args as Args);
-
-    let v_clock_type = args.0;
-
-    // If the struct has named fields, add a v_clock field to it
-    if let syn::Data::Struct(ref mut struct_data) = ast.data {
-        if let syn::Fields::Named(fields) = &mut struct_data.fields {
-            fields.named.push(
-                syn::Field::parse_named
-                    .parse2(quote! { v_clock: crdts::VClock<#v_clock_type> })
-                    .unwrap(),
-            );
-        } else {
-            panic!("`crdt` can only be used on `struct`s that have named fields");
-        }
-    } else {
-        panic!("`crdt` can only be used on `struct`s");
+
+    let expanded = Args::parse_attr(args).and_then(|args| {
+        inject_v_clock(&mut ast, &args)?;
+        let impls = impl_crdt_macro(ast.clone(), &args)?;
+        Ok((args, impls))
+    });
+    if let Ok((args, _)) = &expanded {
+        default_missing_fields(&mut ast, args);
     }
+    // `#[crdt(..)]` on fields is only meaningful to the code generated here
+    strip_field_attrs(&mut ast);
+    let (args, impls) = match expanded {
+        Ok(expanded) => expanded,
+        Err(e) => {
+            // keep the item around so the error is not buried under unresolved names
+            let mut tokens = e.into_compile_error();
+            tokens.extend(ast.into_token_stream());
+            return tokens.into();
+        }
+    };
 
-    // add `CRDT` derive for the struct
+    let derives = args.derives();
     let gen = quote! {
-        #[derive(crdts_macro::CRDT, Default, std::fmt::Debug, Clone, PartialEq, Eq, crdts_macro::serde::Serialize, crdts_macro::serde::Deserialize)]
-        #[serde(crate = "crdts_macro::serde")]
+        #derives
         #ast
+
+        #impls
     };
 
     gen.into()
 }
 
-#[proc_macro_derive(CRDT)]
+#[proc_macro_derive(CRDT, attributes(crdt))]
 pub fn crdt_macro_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let input = syn::parse(input).unwrap();
-    let expanded = impl_crdt_macro(input);
-    proc_macro::TokenStream::from(expanded)
+    let input = parse_macro_input!(input as DeriveInput);
+    Args::from_attrs(&input.attrs)
+        .and_then(|args| impl_crdt_macro(input, &args))
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Add the `v_clock` field to a struct with named fields, unless it is
+/// `embedded` into another one, `field_clocks` to `delta` structs and
+/// `version` to versioned ones.
+fn inject_v_clock(ast: &mut DeriveInput, args: &Args) -> Result<()> {
+    let crdts_macro = args.crdts_macro();
+    let crdts = args.crdts();
+    let actor = &args.actor;
+    let fields = named_fields_mut(ast, "crdt")?;
+    if let Some(field) = fields.named.iter().find(|f| is_field(f, "v_clock")) {
+        return Err(Error::new_spanned(
+            &field.ident,
+            if args.embedded {
+                "`embedded` structs use the `v_clock` of the struct they are part of, remove this field"
+            } else {
+                "`v_clock` is added by `#[crdt]`, remove this field or use `#[derive(CRDT)]`"
+            },
+        ));
+    }
+    if args.embedded {
+        return Ok(());
+    }
+    fields.named.push(syn::Field::parse_named.parse2(quote! {
+        v_clock: #crdts::VClock<#actor>
+    })?);
+    if args.delta {
+        if let Some(field) = fields.named.iter().find(|f| is_field(f, "field_clocks")) {
+            return Err(Error::new_spanned(
+                &field.ident,
+                "`field_clocks` is added by `#[crdt(.., delta)]`, remove this field or use `#[derive(CRDT)]`",
+            ));
+        }
+        fields.named.push(syn::Field::parse_named.parse2(quote! {
+            field_clocks: #crdts_macro::FieldClocks<#actor>
+        })?);
+    }
+    if let Some(version) = &args.version {
+        if let Some(field) = fields.named.iter().find(|f| is_field(f, "version")) {
+            return Err(Error::new_spanned(
+                &field.ident,
+                "`version` is added by `#[crdt(.., version = N)]`, remove this field or use `#[derive(CRDT)]`",
+            ));
+        }
+        fields.named.push(syn::Field::parse_named.parse2(quote! {
+            version: #crdts_macro::Version<#version>
+        })?);
+    }
+    Ok(())
+}
+
+fn named_fields_mut<'a>(ast: &'a mut DeriveInput, macro_name: &str) -> Result<&'a mut FieldsNamed> {
+    let ident = ast.ident.clone();
+    match &mut ast.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => Ok(fields),
+        Data::Struct(DataStruct { fields, .. }) => {
+            let msg =
+                format!("`{macro_name}` can only be used on `struct`s that have named fields");
+            Err(match fields {
+                Fields::Unit => Error::new_spanned(ident, msg),
+                _ => Error::new_spanned(fields, msg),
+            })
+        }
+        Data::Enum(e) => Err(Error::new_spanned(
+            e.enum_token,
+            format!("`{macro_name}` can only be used on `struct`s"),
+        )),
+        Data::Union(u) => Err(Error::new_spanned(
+            u.union_token,
+            format!("`{macro_name}` can only be used on `struct`s"),
+        )),
+    }
+}
+
+/// Let states written before a field was added deserialize, by defaulting
+/// every field that is not skipped and has no `#[serde(default ..)]` yet.
+fn default_missing_fields(ast: &mut DeriveInput, args: &Args) {
+    let serde_default = args.serde_default();
+    if serde_default.is_empty() {
+        return;
+    }
+    if let Data::Struct(DataStruct { fields, .. }) = &mut ast.data {
+        for field in fields {
+            let skip = field_attrs(field).map_or(true, |attrs| attrs.skip);
+            // a missing `version` must not pass for the current one
+            let version = args.version.is_some() && is_field(field, "version");
+            if !skip && !version && !has_serde_default(field) {
+                field.attrs.push(parse_quote!(#serde_default));
+            }
+        }
+    }
+}
+
+fn has_serde_default(field: &Field) -> bool {
+    field
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("serde"))
+        .filter_map(|a| a.meta.require_list().ok())
+        .flat_map(|list| list.tokens.clone())
+        .any(|token| matches!(token, TokenTree::Ident(i) if i == "default"))
+}
+
+fn strip_field_attrs(ast: &mut DeriveInput) {
+    if let Data::Struct(DataStruct { fields, .. }) = &mut ast.data {
+        for field in fields {
+            field.attrs.retain(|a| !a.path().is_ident("crdt"));
+        }
+    }
+}
+
+/// The name of a field without the `r#` of raw identifiers, which is what the
+/// generated names and strings are built from.
+fn ident_string(field: &Field) -> String {
+    field
+        .ident
+        .as_ref()
+        .map(|i| i.unraw().to_string())
+        .unwrap_or_default()
+}
+
+fn is_field(field: &Field, name: &str) -> bool {
+    field.ident.as_ref().is_some_and(|i| i.unraw() == name)
+}
+
+/// The identifier of the field named `f`, raw again if `f` is a keyword.
+pub(crate) fn field_ident(f: &str) -> Ident {
+    syn::parse_str::<Ident>(f).unwrap_or_else(|_| Ident::new_raw(f, Span::call_site()))
+}
+
+/// Reject structs whose expansion would not compile or would silently
+/// shadow generated names.
+fn check_input(input: &mut DeriveInput, args: &Args) -> Result<()> {
+    if let Some(lt) = input.generics.lifetimes().next() {
+        return Err(Error::new_spanned(
+            lt,
+            "`CRDT` does not support lifetime parameters",
+        ));
+    }
+    let ident = input.ident.clone();
+    let fields = named_fields_mut(input, "CRDT")?;
+    let v_clock = fields.named.iter().find(|f| is_field(f, "v_clock"));
+    match v_clock {
+        Some(field) if args.embedded => {
+            return Err(Error::new_spanned(
+                &field.ident,
+                "`embedded` structs use the `v_clock` of the struct they are part of, remove this field",
+            ));
+        }
+        None if !args.embedded => {
+            return Err(Error::new_spanned(
+                ident,
+                "`CRDT` requires a `v_clock: crdts::VClock<_>` field, use `#[crdt(..)]` to add it",
+            ));
+        }
+        _ => {}
+    }
+    if args.delta {
+        if args.embedded {
+            return Err(Error::new(
+                Span::call_site(),
+                "`delta` needs the `v_clock` of the root struct and cannot be combined with `embedded`",
+            ));
+        }
+        if !fields.named.iter().any(|f| is_field(f, "field_clocks")) {
+            return Err(Error::new_spanned(
+                ident,
+                "`delta` requires a `field_clocks: crdts_macro::FieldClocks<_>` field, use `#[crdt(..)]` to add it",
+            ));
+        }
+    }
+
+    if let Some(version) = &args.version {
+        if !fields.named.iter().any(|f| is_field(f, "version")) {
+            return Err(Error::new_spanned(
+                ident,
+                format!("`version = {version}` requires a `version: crdts_macro::Version<{version}>` field, use `#[crdt(..)]` to add it"),
+            ));
+        }
+    }
+
+    // every field becomes an error variant next to `NoneOp`
+    let mut variants = HashMap::from([("NoneOp".to_string(), None)]);
+    let mut tags = HashMap::new();
+    for field in &fields.named {
+        let attrs = field_attrs(field)?;
+        if attrs.skip || args.is_bookkeeping(&ident_string(field)) {
+            continue;
+        }
+        let ident = field.ident.as_ref().unwrap();
+        if let Some(tag) = attrs.tag {
+            if let Some(other) = tags.insert(tag.base10_parse::<u32>()?, ident) {
+                return Err(Error::new_spanned(
+                    &tag,
+                    format!("`{ident}` and `{other}` both have `tag = {tag}`, op tags have to be unique"),
+                ));
+            }
+        }
+        if ident == "dot" {
+            return Err(Error::new_spanned(
+                ident,
+                "`dot` is reserved for the op's dot, rename this field",
+            ));
+        }
+        let variant = ident.unraw().to_string().to_case(Case::Pascal);
+        match variants.insert(variant.clone(), Some(ident)) {
+            Some(Some(other)) => {
+                return Err(Error::new_spanned(
+                    ident,
+                    format!("`{ident}` and `{other}` both map to the `{variant}` error variant, rename one of them"),
+                ))
+            }
+            Some(None) => {
+                return Err(Error::new_spanned(
+                    ident,
+                    format!("`{ident}` collides with the generated `{variant}` variant, rename this field"),
+                ))
+            }
+            None => {}
+        }
+    }
+
+    // each field gets a `field` and a `set_field_op` transaction setter
+    if !args.embedded {
+        let names = fields
+            .named
+            .iter()
+            .filter(|f| !field_attrs(f).is_ok_and(|attrs| attrs.skip))
+            .map(ident_string)
+            .collect::<Vec<_>>();
+        for field in &fields.named {
+            let name = ident_string(field);
+            if let Some(other) = names.iter().find(|other| format!("set_{other}_op") == name) {
+                return Err(Error::new_spanned(
+                    &field.ident,
+                    format!("`{name}` collides with the transaction setter of `{other}`, rename this field"),
+                ));
+            }
+        }
+    }
+    Ok(())
 }
 
-fn impl_crdt_macro(input: syn::DeriveInput) -> TokenStream {
+/// Options of a `#[crdt(..)]` field attribute.
+#[derive(Default)]
+struct FieldAttrs {
+    /// Leave the field out of ops, errors and merges.
+    skip: bool,
+    /// Function merging a skipped field, called as `merge(&mut self.f, other.f)`.
+    merge: Option<Path>,
+    /// Serialized name of the field's op, stable across renames.
+    tag: Option<LitInt>,
+}
+
+fn field_attrs(field: &Field) -> Result<FieldAttrs> {
+    let mut attrs = FieldAttrs::default();
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("crdt")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                attrs.skip = true;
+            } else if meta.path.is_ident("merge") {
+                attrs.merge = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("tag") {
+                let tag = meta.value()?.parse::<LitInt>()?;
+                tag.base10_parse::<u32>()?;
+                attrs.tag = Some(tag);
+            } else {
+                return Err(meta.error("expected `skip`, `merge = \"path::to_fn\"` or `tag = N`"));
+            }
+            Ok(())
+        })?;
+        if attrs.merge.is_some() && !attrs.skip {
+            return Err(Error::new_spanned(
+                attr,
+                "`merge` is only supported together with `skip`",
+            ));
+        }
+        if attrs.tag.is_some() && attrs.skip {
+            return Err(Error::new_spanned(
+                attr,
+                "`tag` names the op of a field, `skip`ped fields have none",
+            ));
+        }
+    }
+    Ok(attrs)
+}
+
+fn impl_crdt_macro(mut input: DeriveInput, args: &Args) -> Result<TokenStream> {
+    check_input(&mut input, args)?;
+
+    let crdts = args.crdts();
+    let serde = args.serde();
+
     let name = &input.ident;
     let data = &input.data;
 
-    let fields = list_fields(data);
+    let mut fields = list_fields(data)?;
+    fields.retain(|(f, _)| !args.is_bookkeeping(f));
+    let skipped = list_skipped(data)?;
+
+    let generics = add_field_bounds(&input.generics, &fields, &crdts);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let op_derives = args.op_derives();
+    let op_serde_bound = if args.no_serde {
+        TokenStream::new()
+    } else {
+        op_serde_bound(&input.generics, &fields, &crdts, &serde)
+    };
 
     let m_error_name = Ident::new(&(name.to_string() + "CmRDTError"), Span::call_site());
-    let m_error_enum = build_m_error(&fields);
+    let m_error_enum = build_m_error(&fields, &crdts);
 
     let v_error_name = Ident::new(&(name.to_string() + "CvRDTError"), Span::call_site());
-    let v_error_enum = build_v_error(&fields);
+    let v_error_enum = build_v_error(&fields, &crdts);
 
     let op_name = Ident::new(&(name.to_string() + "CrdtOp"), Span::call_site());
-    let op_param = build_op(&fields);
+    let field_op_name = Ident::new(&(name.to_string() + "FieldOp"), Span::call_site());
+    let field_op = args.enum_ops.then_some(&field_op_name);
+    let op_tags = list_tags(data)?
+        .into_iter()
+        .map(|(field, tag)| (field, args.op_tag(&tag)))
+        .collect();
+    let op_param = match field_op {
+        Some(field_op) => build_enum_op(
+            &fields,
+            &crdts,
+            &args.serde_default(),
+            field_op,
+            &ty_generics,
+        ),
+        None => build_op(&fields, &crdts, &args.op_slot_serde(), &op_tags),
+    };
+    let field_op_enum = field_op.map(|field_op| {
+        let field_op_derives = args.field_op_derives();
+        let variants = build_field_op(&fields, &crdts, &op_tags);
+        let doc = format!("The op of one field of a [`{name}`], carried in `{op_name}::ops`.");
+        quote! {
+            #[doc = #doc]
+            #[allow(clippy::type_complexity)]
+            #field_op_derives
+            #op_serde_bound
+            pub enum #field_op #generics #where_clause {
+                #variants
+            }
+        }
+    });
+    let op_version = args.version.as_ref().map(|version| {
+        let crdts_macro = args.crdts_macro();
+        quote!(pub version: #crdts_macro::Version<#version>,)
+    });
 
-    let impl_apply = impl_apply(&fields);
-    let impl_validate = impl_validate(&fields);
+    let impl_apply = impl_apply(&fields, args.embedded, args.delta, field_op);
+    let impl_validate = impl_validate(&fields, args.embedded, field_op);
 
-    let impl_merge = impl_merge(&fields);
+    let impl_merge = impl_merge(&fields, &skipped, args.delta);
     let impl_validate_merge = impl_validate_merge(&fields);
 
-    quote! {
+    let actor = actor_type(args, &fields);
+    let impl_reset_remove = match &actor {
+        Some(actor) => impl_reset_remove(name, &generics, &fields, actor, args),
+        None => TokenStream::new(),
+    };
+    let impl_try_apply = if args.embedded {
+        TokenStream::new()
+    } else {
+        impl_try_apply(name, &generics, &args.crdts_macro(), &crdts)
+    };
+    let impl_causal = match &actor {
+        Some(actor) if !args.embedded => {
+            impl_causal(name, &generics, actor, &args.crdts_macro(), &crdts)
+        }
+        _ => TokenStream::new(),
+    };
+    let impl_delta = match &actor {
+        Some(actor) if args.delta => delta::impl_delta(name, &generics, &fields, actor, args),
+        Some(actor) if !args.embedded => delta::impl_full_delta(name, &generics, actor, args),
+        _ => TokenStream::new(),
+    };
+    let impl_migrate = version::impl_migrate(name, &generics, args);
+    let impl_fields = field::impl_fields(name, &generics, &fields, &args.crdts_macro());
+    let impl_read_ctx = match &actor {
+        Some(actor) if args.read_ctx => {
+            read_ctx::impl_read_ctx(name, &generics, &fields, actor, args)
+        }
+        None if args.read_ctx => {
+            return Err(Error::new(
+                Span::call_site(),
+                "`read_ctx` needs the actor type, e.g. `#[crdt(u64, read_ctx)]`",
+            ))
+        }
+        _ => TokenStream::new(),
+    };
+    let impl_intent = match &actor {
+        Some(actor) if args.intents => intent::impl_intent(name, &generics, &fields, actor, args),
+        None if args.intents => {
+            return Err(Error::new(
+                Span::call_site(),
+                "`intents` needs the actor type, e.g. `#[crdt(u64, intents)]`",
+            ))
+        }
+        _ => TokenStream::new(),
+    };
+    let impl_view = if args.view {
+        view::impl_view(name, &generics, &fields, args)
+    } else {
+        TokenStream::new()
+    };
+    let impl_changes = if args.embedded {
+        TokenStream::new()
+    } else {
+        let changes = changes::impl_changes(name, &generics, &fields, args);
+        let observer = observer::impl_observer(name, &generics, &fields);
+        quote!(#changes #observer)
+    };
+    let impl_transact = match &actor {
+        Some(actor) if !args.embedded => {
+            transact::impl_transact(name, &generics, &fields, actor, args)
+        }
+        _ => TokenStream::new(),
+    };
+    let impl_transact_ctx = match &actor {
+        Some(actor) => transact::impl_transact_ctx(name, &generics, actor, args),
+        None => TokenStream::new(),
+    };
+
+    Ok(quote! {
         #[derive(std::fmt::Debug, PartialEq, Eq)]
-        pub enum #m_error_name {
+        pub enum #m_error_name #generics #where_clause {
             NoneOp,
             #m_error_enum
         }
 
-        impl std::fmt::Display for #m_error_name {
+        impl #impl_generics std::fmt::Display for #m_error_name #ty_generics #where_clause {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 std::fmt::Debug::fmt(&self, f)
             }
         }
 
-        impl std::error::Error for #m_error_name {}
+        impl #impl_generics std::error::Error for #m_error_name #ty_generics #where_clause {}
 
         #[allow(clippy::type_complexity)]
-        #[derive(std::fmt::Debug, Clone, PartialEq, Eq, crdts_macro::serde::Serialize, crdts_macro::serde::Deserialize)]
-        #[serde(crate = "crdts_macro::serde")]
-        pub struct #op_name {
+        #op_derives
+        #op_serde_bound
+        pub struct #op_name #generics #where_clause {
             #op_param
+            #op_version
         }
 
-        impl crdts::CmRDT for #name {
-            type Op = #op_name;
-            type Validation = #m_error_name;
+        #field_op_enum
+
+        impl #impl_generics #crdts::CmRDT for #name #ty_generics #where_clause {
+            type Op = #op_name #ty_generics;
+            type Validation = #m_error_name #ty_generics;
 
             fn apply(&mut self, op: Self::Op) {
                 #impl_apply
@@ -113,20 +536,20 @@ fn impl_crdt_macro(input: syn::DeriveInput) -> TokenStream {
         }
 
         #[derive(std::fmt::Debug, PartialEq, Eq)]
-        pub enum #v_error_name {
+        pub enum #v_error_name #generics #where_clause {
             #v_error_enum
         }
 
-        impl std::fmt::Display for #v_error_name {
+        impl #impl_generics std::fmt::Display for #v_error_name #ty_generics #where_clause {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 std::fmt::Debug::fmt(&self, f)
             }
         }
 
-        impl std::error::Error for #v_error_name {}
+        impl #impl_generics std::error::Error for #v_error_name #ty_generics #where_clause {}
 
-        impl crdts::CvRDT for #name {
-            type Validation = #v_error_name;
+        impl #impl_generics #crdts::CvRDT for #name #ty_generics #where_clause {
+            type Validation = #v_error_name #ty_generics;
 
             fn validate_merge(&self, other: &Self) -> Result<(), Self::Validation> {
                 #impl_validate_merge
@@ -137,54 +560,260 @@ fn impl_crdt_macro(input: syn::DeriveInput) -> TokenStream {
                 #impl_merge
             }
         }
+
+        #impl_reset_remove
+
+        #impl_try_apply
+
+        #impl_causal
+
+        #impl_transact
+
+        #impl_transact_ctx
+
+        #impl_delta
+
+        #impl_migrate
+
+        #impl_changes
+
+        #impl_fields
+
+        #impl_view
+
+        #impl_read_ctx
+
+        #impl_intent
+    })
+}
+
+/// The actor type, given as `#[crdt(A)]` or taken from `v_clock: VClock<A>`.
+fn actor_type(args: &Args, fields: &[(String, Type)]) -> Option<Type> {
+    if let Some(actor) = &args.actor {
+        return Some(actor.clone());
+    }
+    let (_, Type::Path(v_clock)) = fields.iter().find(|(f, _)| f == "v_clock")? else {
+        return None;
+    };
+    match &v_clock.path.segments.last()?.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(actor) => Some(actor.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `ResetRemove` lets the struct be used as a `crdts::Map` value. Fields
+/// without a `ResetRemove` impl (e.g. `LWWReg`) only make the impl unusable
+/// instead of failing to compile, hence the higher-ranked bounds.
+fn impl_reset_remove(
+    name: &Ident,
+    generics: &Generics,
+    fields: &[(String, Type)],
+    actor: &Type,
+    args: &Args,
+) -> TokenStream {
+    let crdts = args.crdts();
+    let mut generics = generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .extend(fields.iter().map(|(_, ty)| -> WherePredicate {
+            parse_quote!(for<'__crdt> #ty: #crdts::ResetRemove<#actor>)
+        }));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let reset_remove = fields.iter().map(|(f, ty)| {
+        let field = field_ident(f);
+        quote! {
+            <#ty as #crdts::ResetRemove<#actor>>::reset_remove(&mut self.#field, clock);
+        }
+    });
+    let reset_field_clocks = args
+        .delta
+        .then(|| quote!(#crdts::ResetRemove::reset_remove(&mut self.field_clocks, clock);));
+    quote! {
+        impl #impl_generics #crdts::ResetRemove<#actor> for #name #ty_generics #where_clause {
+            fn reset_remove(&mut self, clock: &#crdts::VClock<#actor>) {
+                #(#reset_remove)*
+                #reset_field_clocks
+            }
+        }
     }
 }
 
-fn list_fields(data: &Data) -> HashMap<String, Type> {
+/// Named fields in declaration order, so the op layout, the error variants and
+/// the merge order are the same for every build. `#[crdt(skip)]` fields are
+/// left out.
+fn list_fields(data: &Data) -> Result<Vec<(String, Type)>> {
+    let mut list = Vec::new();
     if let Data::Struct(DataStruct {
         fields: Fields::Named(fields),
         ..
     }) = data
     {
-        fields
-            .named
+        for f in &fields.named {
+            if !field_attrs(f)?.skip {
+                list.push((ident_string(f), f.ty.clone()));
+            }
+        }
+    }
+    Ok(list)
+}
+
+/// `#[crdt(skip)]` fields with their optional merge function.
+fn list_skipped(data: &Data) -> Result<Vec<(String, Option<Path>)>> {
+    let mut list = Vec::new();
+    if let Data::Struct(DataStruct {
+        fields: Fields::Named(fields),
+        ..
+    }) = data
+    {
+        for f in &fields.named {
+            let attrs = field_attrs(f)?;
+            if attrs.skip {
+                list.push((ident_string(f), attrs.merge));
+            }
+        }
+    }
+    Ok(list)
+}
+
+/// `#[crdt(tag = N)]` of the fields that have one.
+fn list_tags(data: &Data) -> Result<HashMap<String, LitInt>> {
+    let mut tags = HashMap::new();
+    if let Data::Struct(DataStruct {
+        fields: Fields::Named(fields),
+        ..
+    }) = data
+    {
+        for f in &fields.named {
+            if let Some(tag) = field_attrs(f)?.tag {
+                tags.insert(ident_string(f), tag);
+            }
+        }
+    }
+    Ok(tags)
+}
+
+/// Carries the bounds the generated items need on every field type over to
+/// the struct generics. Non-generic structs are left untouched, their field
+/// types are checked directly by the compiler.
+///
+/// `v_clock` is left out on purpose: a `VClock<A>: CmRDT` predicate would stop
+/// the compiler from normalizing its op to `Dot<A>`.
+fn add_field_bounds(generics: &Generics, fields: &[(String, Type)], crdts: &Path) -> Generics {
+    let mut generics = generics.clone();
+    if generics.params.is_empty() {
+        return generics;
+    }
+    let predicates = fields.iter().filter(|(f, _)| *f != "v_clock").flat_map(
+        |(_, ty)| -> [WherePredicate; 4] {
+            [
+                parse_quote!(#ty: #crdts::CmRDT + #crdts::CvRDT),
+                parse_quote!(<#ty as #crdts::CmRDT>::Op: std::fmt::Debug + Clone + PartialEq + Eq),
+                parse_quote!(<#ty as #crdts::CmRDT>::Validation: std::fmt::Debug + PartialEq + Eq),
+                parse_quote!(<#ty as #crdts::CvRDT>::Validation: std::fmt::Debug + PartialEq + Eq),
+            ]
+        },
+    );
+    generics.make_where_clause().predicates.extend(predicates);
+    generics
+}
+
+/// serde cannot infer bounds through `<T as CmRDT>::Op`, so generic op
+/// structs spell them out.
+fn op_serde_bound(
+    generics: &Generics,
+    fields: &[(String, Type)],
+    crdts: &Path,
+    serde: &Path,
+) -> TokenStream {
+    let ops = fields
+        .iter()
+        .map(|(_, ty)| quote!(<#ty as #crdts::CmRDT>::Op))
+        .collect::<Vec<_>>();
+    serde_bound(generics, &ops, serde, true)
+}
+
+/// The `#[serde(bound(..))]` of a generated type holding `types`, for the
+/// projections serde cannot infer bounds through. Only `Serialize` is bound
+/// unless `deserialize` is set, and non-generic types need no bound at all.
+pub(crate) fn serde_bound(
+    generics: &Generics,
+    types: &[TokenStream],
+    serde: &Path,
+    deserialize: bool,
+) -> TokenStream {
+    if generics.params.is_empty() {
+        return TokenStream::new();
+    }
+    let bound = |bound: TokenStream| {
+        types
             .iter()
-            .map(|f| (f.ident.as_ref().unwrap().to_string(), f.ty.clone()))
-            .collect()
-    } else {
-        HashMap::new()
+            .map(|ty| quote!(#ty: #bound,))
+            .collect::<TokenStream>()
+            .to_string()
+    };
+    let ser = bound(quote!(#serde::Serialize));
+    if !deserialize {
+        return quote!(#[serde(bound(serialize = #ser))]);
+    }
+    let de = bound(quote!(#serde::Deserialize<'de>));
+    quote!(#[serde(bound(serialize = #ser, deserialize = #de))])
+}
+
+/// Add `bounds` on each of the projected `types` to a generic `generics`, as
+/// the derives of a type holding them don't look through the projection.
+pub(crate) fn projection_bounds(
+    generics: &mut Generics,
+    types: &[TokenStream],
+    bounds: TokenStream,
+) {
+    if generics.params.is_empty() {
+        return;
+    }
+    let predicates = &mut generics.make_where_clause().predicates;
+    for ty in types {
+        predicates.push(parse_quote!(#ty: #bounds));
     }
 }
 
-fn build_m_error(fields: &HashMap<String, Type>) -> TokenStream {
+fn build_m_error(fields: &[(String, Type)], crdts: &Path) -> TokenStream {
     fields
         .iter()
         .map(|(field_name, field_type)| {
             let pascal_name = field_name.to_case(Case::Pascal);
             let name = Ident::new(&pascal_name, Span::call_site());
             quote_spanned! { Span::call_site() =>
-                #name(<#field_type as crdts::CmRDT>::Validation),
+                #name(<#field_type as #crdts::CmRDT>::Validation),
             }
         })
         .collect::<TokenStream>()
 }
 
-fn build_v_error(fields: &HashMap<String, Type>) -> TokenStream {
+fn build_v_error(fields: &[(String, Type)], crdts: &Path) -> TokenStream {
     fields
         .iter()
         .map(|(name, ty)| {
             let pascal_name = name.to_case(Case::Pascal);
             let name = Ident::new(&pascal_name, Span::call_site());
             quote_spanned! { Span::call_site() =>
-                #name(<#ty as crdts::CvRDT>::Validation),
+                #name(<#ty as #crdts::CvRDT>::Validation),
             }
         })
         .collect::<TokenStream>()
 }
 
-fn build_op(fields: &HashMap<String, Type>) -> TokenStream {
+fn build_op(
+    fields: &[(String, Type)],
+    crdts: &Path,
+    slot_serde: &TokenStream,
+    tags: &HashMap<String, TokenStream>,
+) -> TokenStream {
     let mut tokens = TokenStream::new();
     for (name, ty) in fields {
+        let tag = tags.get(name);
         let (name, is_vclock) = if name == "v_clock" {
             (Ident::new("dot", Span::call_site()), true)
         } else {
@@ -193,68 +822,271 @@ fn build_op(fields: &HashMap<String, Type>) -> TokenStream {
                 false,
             )
         };
-        let op_type = if is_vclock {
-            quote! {<#ty as crdts::CmRDT>::Op}
+        let (op_type, default) = if is_vclock {
+            (quote! {<#ty as #crdts::CmRDT>::Op}, None)
         } else {
-            quote! {Option<<#ty as crdts::CmRDT>::Op>}
+            (
+                quote! {Option<<#ty as #crdts::CmRDT>::Op>},
+                Some(slot_serde),
+            )
         };
         tokens.extend(quote_spanned! {Span::call_site() =>
+            #default
+            #tag
             pub #name: #op_type,
         });
     }
     tokens
 }
 
-fn impl_apply(fields: &HashMap<String, Type>) -> TokenStream {
+/// The slots of an `op = "enum"` op: its dot and the field ops in order.
+fn build_enum_op(
+    fields: &[(String, Type)],
+    crdts: &Path,
+    serde_default: &TokenStream,
+    field_op: &Ident,
+    ty_generics: &TypeGenerics,
+) -> TokenStream {
+    let dot = fields
+        .iter()
+        .find(|(f, _)| f == "v_clock")
+        .map(|(_, ty)| quote!(pub dot: <#ty as #crdts::CmRDT>::Op,));
+    quote! {
+        #dot
+        #serde_default
+        pub ops: Vec<#field_op #ty_generics>,
+    }
+}
+
+fn build_field_op(
+    fields: &[(String, Type)],
+    crdts: &Path,
+    tags: &HashMap<String, TokenStream>,
+) -> TokenStream {
+    fields
+        .iter()
+        .filter(|(f, _)| f != "v_clock")
+        .map(|(f, ty)| {
+            let variant = Ident::new(&f.to_case(Case::Pascal), Span::call_site());
+            let tag = tags.get(f);
+            quote!(#tag #variant(<#ty as #crdts::CmRDT>::Op),)
+        })
+        .collect()
+}
+
+fn impl_apply(
+    fields: &[(String, Type)],
+    embedded: bool,
+    delta: bool,
+    field_op: Option<&Ident>,
+) -> TokenStream {
+    if let Some(field_op) = field_op {
+        return impl_apply_enum(fields, embedded, delta, field_op);
+    }
     let op_params = op_params(fields);
     let nones = count_none(fields);
 
-    let apply = fields.keys().filter(|f| *f != "v_clock").map(|f| {
-        let field = Ident::new(f, Span::call_site());
-        let op = Ident::new(&(f.to_owned() + "_op"), Span::call_site());
+    let apply = fields
+        .iter()
+        .map(|(f, _)| f)
+        .filter(|f| *f != "v_clock")
+        .map(|f| {
+            let field = field_ident(f);
+            let op = Ident::new(&(f.to_owned() + "_op"), Span::call_site());
+            let track = delta.then(|| quote!(self.field_clocks.apply(#f, dot.clone());));
+
+            quote_spanned! { Span::call_site() =>
+                if let Some(#op) = #op {
+                    self.#field.apply(#op);
+                    #track
+                }
+            }
+        });
 
-        quote_spanned! { Span::call_site() =>
-            if let Some(#op) = #op {
-                self.#field.apply(#op);
+    if embedded {
+        // the dot has already been checked by the struct holding this one
+        return quote! {
+            let Self::Op { #op_params } = op;
+            match (#op_params) {
+                (#nones) => (),
+                (#op_params) => { #(#apply)* }
             }
-        }
-    });
+        };
+    }
 
     quote! {
-        let Self::Op { dot, #op_params } = op;
+        // `..` skips the `version` of versioned ops
+        let Self::Op { dot, #op_params .. } = op;
         if self.v_clock.get(&dot.actor) >= dot.counter {
             return;
         }
+        // an op left empty by dropping unknown field ops still takes its dot,
+        // later ops of the actor would not validate otherwise
         match (#op_params) {
-            (#nones) => return,
+            (#nones) => (),
             (#op_params) => { #(#apply)* }
         }
         self.v_clock.apply(dot);
     }
 }
 
-fn impl_validate(fields: &HashMap<String, Type>) -> TokenStream {
+/// `impl_apply` for `op = "enum"`, applying the field ops in order.
+fn impl_apply_enum(
+    fields: &[(String, Type)],
+    embedded: bool,
+    delta: bool,
+    field_op: &Ident,
+) -> TokenStream {
+    let arms = fields
+        .iter()
+        .map(|(f, _)| f)
+        .filter(|f| *f != "v_clock")
+        .map(|f| {
+            let field = field_ident(f);
+            let variant = Ident::new(&f.to_case(Case::Pascal), Span::call_site());
+            let track = delta.then(|| quote!(self.field_clocks.apply(#f, dot.clone());));
+            quote! {
+                #field_op::#variant(op) => {
+                    self.#field.apply(op);
+                    #track
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    let apply = quote! {
+        for op in ops {
+            match op {
+                #(#arms)*
+            }
+        }
+    };
+
+    if embedded {
+        // the dot has already been checked by the struct holding this one
+        return quote! {
+            let Self::Op { ops } = op;
+            #apply
+        };
+    }
+
+    quote! {
+        // `..` skips the `version` of versioned ops
+        let Self::Op { dot, ops, .. } = op;
+        // an empty op still takes its dot, see `impl_apply`
+        if self.v_clock.get(&dot.actor) >= dot.counter {
+            return;
+        }
+        #apply
+        self.v_clock.apply(dot);
+    }
+}
+
+fn impl_try_apply(
+    name: &Ident,
+    generics: &Generics,
+    crdts_macro: &Path,
+    crdts: &Path,
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let m_error_name = Ident::new(&(name.to_string() + "CmRDTError"), Span::call_site());
+    let op_name = Ident::new(&(name.to_string() + "CrdtOp"), Span::call_site());
+
+    quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Validate and apply `op`, reporting what happened to it.
+            pub fn try_apply(
+                &mut self,
+                op: #op_name #ty_generics,
+            ) -> #crdts_macro::ApplyOutcome<#m_error_name #ty_generics> {
+                if self.v_clock.get(&op.dot.actor) >= op.dot.counter {
+                    return #crdts_macro::ApplyOutcome::Duplicate;
+                }
+                match #crdts::CmRDT::validate_op(self, &op) {
+                    Ok(()) => {
+                        #crdts::CmRDT::apply(self, op);
+                        #crdts_macro::ApplyOutcome::Applied
+                    }
+                    Err(#m_error_name::NoneOp) => {
+                        #crdts::CmRDT::apply(self, op);
+                        #crdts_macro::ApplyOutcome::Empty
+                    }
+                    Err(e) => #crdts_macro::ApplyOutcome::Invalid(e),
+                }
+            }
+        }
+    }
+}
+
+fn impl_causal(
+    name: &Ident,
+    generics: &Generics,
+    actor: &Type,
+    crdts_macro: &Path,
+    crdts: &Path,
+) -> TokenStream {
+    let mut generics = generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(#actor: #crdts::Actor + std::fmt::Debug));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #crdts_macro::Causal for #name #ty_generics #where_clause {
+            type Actor = #actor;
+
+            fn clock(&self) -> &#crdts::VClock<#actor> {
+                &self.v_clock
+            }
+
+            fn dot(op: &Self::Op) -> &#crdts::Dot<#actor> {
+                &op.dot
+            }
+        }
+    }
+}
+
+fn impl_validate(
+    fields: &[(String, Type)],
+    embedded: bool,
+    field_op: Option<&Ident>,
+) -> TokenStream {
+    if let Some(field_op) = field_op {
+        return impl_validate_enum(fields, embedded, field_op);
+    }
     let op_params = op_params(fields);
     let nones = count_none(fields);
 
-    let validate = fields.keys().filter(|f| f != &"v_clock").map(|f| {
-        let pascal_name = f.to_case(Case::Pascal);
-        let error_name = Ident::new(&pascal_name, Span::call_site());
-        let field = Ident::new(f, Span::call_site());
-        let op = Ident::new(&(f.to_owned() + "_op"), Span::call_site());
-        quote_spanned! { Span::call_site() =>
-            if let Some(#op) = #op {
-                self.#field.validate_op(#op).map_err(Self::Validation::#error_name)?;
+    let validate = fields
+        .iter()
+        .map(|(f, _)| f)
+        .filter(|f| f != &"v_clock")
+        .map(|f| {
+            let pascal_name = f.to_case(Case::Pascal);
+            let error_name = Ident::new(&pascal_name, Span::call_site());
+            let field = field_ident(f);
+            let op = Ident::new(&(f.to_owned() + "_op"), Span::call_site());
+            quote_spanned! { Span::call_site() =>
+                if let Some(#op) = #op {
+                    self.#field.validate_op(#op).map_err(Self::Validation::#error_name)?;
+                }
             }
-        }
-    });
+        });
+
+    let validate_dot = if embedded {
+        TokenStream::new()
+    } else {
+        quote!(self.v_clock.validate_op(dot).map_err(Self::Validation::VClock)?;)
+    };
+    let dot = if embedded { quote!() } else { quote!(dot,) };
 
     quote! {
         let Self::Op {
-            dot,
+            #dot
             #op_params
+            ..
         } = op;
-        self.v_clock.validate_op(dot).map_err(Self::Validation::VClock)?;
+        #validate_dot
         match (#op_params) {
             (#nones) => return Err(Self::Validation::NoneOp),
             (#op_params) => {
@@ -265,24 +1097,88 @@ fn impl_validate(fields: &HashMap<String, Type>) -> TokenStream {
     }
 }
 
-fn impl_merge(fields: &HashMap<String, Type>) -> TokenStream {
-    fields
-        .keys()
+/// `impl_validate` for `op = "enum"`, validating every field op against the
+/// current state.
+fn impl_validate_enum(fields: &[(String, Type)], embedded: bool, field_op: &Ident) -> TokenStream {
+    let arms = fields
+        .iter()
+        .map(|(f, _)| f)
+        .filter(|f| *f != "v_clock")
         .map(|f| {
-            let field = Ident::new(f, Span::call_site());
-            quote_spanned! {
-                Span::call_site() => self.#field.merge(other.#field);
+            let field = field_ident(f);
+            let variant = Ident::new(&f.to_case(Case::Pascal), Span::call_site());
+            quote! {
+                #field_op::#variant(op) => {
+                    self.#field.validate_op(op).map_err(Self::Validation::#variant)?;
+                }
             }
         })
-        .collect()
+        .collect::<Vec<_>>();
+    // references to an empty enum are not considered uninhabited
+    let op = if arms.is_empty() {
+        quote!(*op)
+    } else {
+        quote!(op)
+    };
+
+    let (dot, validate_dot) = if embedded {
+        (None, None)
+    } else {
+        (
+            Some(quote!(dot,)),
+            Some(quote!(self.v_clock.validate_op(dot).map_err(Self::Validation::VClock)?;)),
+        )
+    };
+
+    quote! {
+        let Self::Op { #dot ops, .. } = op;
+        #validate_dot
+        if ops.is_empty() {
+            return Err(Self::Validation::NoneOp);
+        }
+        for op in ops {
+            match #op {
+                #(#arms)*
+            }
+        }
+        Ok(())
+    }
 }
 
-fn impl_validate_merge(fields: &HashMap<String, Type>) -> TokenStream {
+fn impl_merge(
+    fields: &[(String, Type)],
+    skipped: &[(String, Option<Path>)],
+    delta: bool,
+) -> TokenStream {
+    let merge = fields.iter().map(|(f, _)| {
+        let field = field_ident(f);
+        quote_spanned! {
+            Span::call_site() => self.#field.merge(other.#field);
+        }
+    });
+    let merge_skipped = skipped.iter().filter_map(|(f, merge_fn)| {
+        let field = field_ident(f);
+        merge_fn.as_ref().map(|merge_fn| {
+            quote! {
+                #merge_fn(&mut self.#field, other.#field);
+            }
+        })
+    });
+    let merge_field_clocks = delta.then(|| quote!(self.field_clocks.merge(other.field_clocks);));
+    quote! {
+        #(#merge)*
+        #merge_field_clocks
+        #(#merge_skipped)*
+    }
+}
+
+fn impl_validate_merge(fields: &[(String, Type)]) -> TokenStream {
     fields
-        .keys()
+        .iter()
+        .map(|(f, _)| f)
         .map(|field| {
             let error_name = Ident::new(&field.to_case(Case::Pascal), Span::call_site());
-            let field = Ident::new(field, Span::call_site());
+            let field = field_ident(field);
             quote! {
                 self.#field.validate_merge(&other.#field)
                     .map_err(Self::Validation::#error_name)?;
@@ -291,9 +1187,10 @@ fn impl_validate_merge(fields: &HashMap<String, Type>) -> TokenStream {
         .collect()
 }
 
-fn count_none(fields: &HashMap<String, Type>) -> TokenStream {
+fn count_none(fields: &[(String, Type)]) -> TokenStream {
     fields
-        .keys()
+        .iter()
+        .map(|(f, _)| f)
         .filter(|&f| f != "v_clock")
         .map(|_| quote!(None,))
         .collect::<Vec<_>>()
@@ -301,9 +1198,10 @@ fn count_none(fields: &HashMap<String, Type>) -> TokenStream {
         .collect::<TokenStream>()
 }
 
-fn op_params(fields: &HashMap<String, Type>) -> TokenStream {
+fn op_params(fields: &[(String, Type)]) -> TokenStream {
     fields
-        .keys()
+        .iter()
+        .map(|(f, _)| f)
         .filter(|f| *f != "v_clock")
         .map(|f| format!("{}_op", f))
         .map(|i| Ident::new(&i, Span::call_site()))
@@ -0,0 +1,90 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Generics, Type, WherePredicate};
+
+use crate::field_ident;
+
+/// The `DataObserver` trait with an `on_x_changed` hook per field, and
+/// `apply_observed` / `merge_observed` calling the hooks of the fields
+/// reported by `apply_with_changes` / `merge_with_changes`. These are the only
+/// two paths calling the hooks, the runtime helpers applying ops on their own
+/// (`CausalBuffer::deliver`, `sync::Replica`, `OpLog::restore`) don't. Like
+/// those of `impl_changes`, the bounds on the fields are higher-ranked.
+pub(crate) fn impl_observer(
+    name: &Ident,
+    generics: &Generics,
+    fields: &[(String, Type)],
+) -> TokenStream {
+    let observer_name = Ident::new(&(name.to_string() + "Observer"), Span::call_site());
+    let op_name = Ident::new(&(name.to_string() + "CrdtOp"), Span::call_site());
+
+    let fields = fields
+        .iter()
+        .filter(|(f, _)| f != "v_clock")
+        .map(|(f, ty)| {
+            (
+                field_ident(f),
+                Ident::new(&format!("on_{f}_changed"), Span::call_site()),
+                ty,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+    let mut impl_generics = generics.clone();
+    impl_generics.make_where_clause().predicates.extend(
+        fields.iter().map(|(_, _, ty)| -> WherePredicate {
+            parse_quote!(for<'__crdt> #ty: PartialEq + Clone)
+        }),
+    );
+    let (impl_generics, _, impl_where_clause) = impl_generics.split_for_impl();
+
+    let hooks = fields.iter().map(|(field, hook, ty)| {
+        let doc = format!("Called with the new value of `{field}` after it changed.");
+        quote! {
+            #[doc = #doc]
+            #[allow(unused_variables)]
+            fn #hook(&mut self, #field: &#ty) {}
+        }
+    });
+    let notify = fields.iter().map(|(field, hook, _)| {
+        quote! {
+            if changes.#field {
+                observer.#hook(&self.#field);
+            }
+        }
+    });
+    let notify = quote!(#(#notify)*);
+
+    let observer_doc = format!(
+        "Hooks called by [`{name}::apply_observed`] and [`{name}::merge_observed`] for every changed field, all of them do nothing by default. Ops applied by `CausalBuffer::deliver`, `sync::Replica` or `OpLog::restore` are not observed, drain the buffer with `pop_ready` and `apply_observed` instead."
+    );
+    quote! {
+        #[doc = #observer_doc]
+        pub trait #observer_name #generics #where_clause {
+            #(#hooks)*
+        }
+
+        impl #impl_generics #name #ty_generics #impl_where_clause {
+            /// Apply `op` and notify `observer` of the fields it changed.
+            pub fn apply_observed(
+                &mut self,
+                op: #op_name #ty_generics,
+                observer: &mut impl #observer_name #ty_generics,
+            ) {
+                let changes = self.apply_with_changes(op);
+                #notify
+            }
+
+            /// Merge `other` and notify `observer` of the fields it changed.
+            pub fn merge_observed(
+                &mut self,
+                other: Self,
+                observer: &mut impl #observer_name #ty_generics,
+            ) {
+                let changes = self.merge_with_changes(other);
+                #notify
+            }
+        }
+    }
+}
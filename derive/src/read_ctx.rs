@@ -0,0 +1,96 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Generics, Type};
+
+use crate::args::Args;
+use crate::{field_ident, projection_bounds, serde_bound};
+
+/// `DataReadCtx` holding the `HasReadCtx::ReadCtx` of every field and the
+/// root `v_clock`, and the `HasReadCtx` impl reading it.
+pub(crate) fn impl_read_ctx(
+    name: &Ident,
+    generics: &Generics,
+    fields: &[(String, Type)],
+    actor: &Type,
+    args: &Args,
+) -> TokenStream {
+    let crdts_macro = args.crdts_macro();
+    let crdts = args.crdts();
+    let ctx_name = Ident::new(&(name.to_string() + "ReadCtx"), Span::call_site());
+    let ctx_derives = args.read_ctx_derives();
+
+    let fields = fields
+        .iter()
+        .filter(|(f, _)| f != "v_clock")
+        .map(|(f, ty)| (field_ident(f), ty))
+        .collect::<Vec<_>>();
+    let has_read_ctx = quote!(#crdts_macro::HasReadCtx<#actor>);
+
+    let ctxs = fields
+        .iter()
+        .map(|(_, ty)| quote!(<#ty as #has_read_ctx>::ReadCtx))
+        .collect::<Vec<_>>();
+    let mut generics = generics.clone();
+    let predicates = &mut generics.make_where_clause().predicates;
+    for (_, ty) in &fields {
+        predicates.push(parse_quote!(#ty: #has_read_ctx));
+    }
+    projection_bounds(
+        &mut generics,
+        &ctxs,
+        quote!(std::fmt::Debug + PartialEq + Eq),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let serde_bound = (!args.no_serde).then(|| {
+        let v_clock = (!args.embedded).then(|| quote!(#crdts::VClock<#actor>));
+        let types = ctxs.iter().cloned().chain(v_clock).collect::<Vec<_>>();
+        serde_bound(&generics, &types, &args.serde(), true)
+    });
+    let slots = fields
+        .iter()
+        .map(|(field, ty)| quote!(pub #field: <#ty as #has_read_ctx>::ReadCtx,));
+    let reads = fields
+        .iter()
+        .map(|(field, ty)| quote!(#field: <#ty as #has_read_ctx>::read_ctx(&self.#field),));
+    let (v_clock_slot, v_clock_read) = if args.embedded {
+        (None, None)
+    } else {
+        (
+            Some(quote!(pub v_clock: #crdts::VClock<#actor>,)),
+            Some(quote!(v_clock: self.v_clock.clone(),)),
+        )
+    };
+
+    let ctx_doc = format!(
+        "The causal context of every field of a [`{name}`], enough to build its ops elsewhere, see `{name}::read_ctx`."
+    );
+    quote! {
+        #[doc = #ctx_doc]
+        #[allow(clippy::type_complexity)]
+        #ctx_derives
+        #serde_bound
+        pub struct #ctx_name #generics #where_clause {
+            #(#slots)*
+            #v_clock_slot
+        }
+
+        impl #impl_generics #has_read_ctx for #name #ty_generics #where_clause {
+            type ReadCtx = #ctx_name #ty_generics;
+
+            fn read_ctx(&self) -> Self::ReadCtx {
+                #ctx_name {
+                    #(#reads)*
+                    #v_clock_read
+                }
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Read the causal context of every field at once.
+            pub fn read_ctx(&self) -> #ctx_name #ty_generics {
+                <Self as #has_read_ctx>::read_ctx(self)
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Generics, Type};
+
+use crate::args::Args;
+use crate::field_ident;
+
+/// `Data::transact` and the `DataTransaction` it hands out, which collect the
+/// ops of the touched fields into a single `DataCrdtOp` under the next dot of
+/// the actor. With `op = "enum"` the field ops are kept in the order the
+/// fields are first set.
+pub(crate) fn impl_transact(
+    name: &Ident,
+    generics: &Generics,
+    fields: &[(String, Type)],
+    actor: &Type,
+    args: &Args,
+) -> TokenStream {
+    let crdts = args.crdts();
+    let crdts_macro = args.crdts_macro();
+    let tx_name = Ident::new(&(name.to_string() + "Transaction"), Span::call_site());
+    let op_name = Ident::new(&(name.to_string() + "CrdtOp"), Span::call_site());
+    let field_op = Ident::new(&(name.to_string() + "FieldOp"), Span::call_site());
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut tx_generics = generics.clone();
+    tx_generics.params.insert(0, parse_quote!('a));
+    let (tx_impl_generics, tx_ty_generics, _) = tx_generics.split_for_impl();
+
+    let fields = fields
+        .iter()
+        .filter(|(f, _)| f != "v_clock")
+        .map(|(f, ty)| {
+            (
+                field_ident(f),
+                Ident::new(&format!("{f}_op"), Span::call_site()),
+                Ident::new(&format!("set_{f}_op"), Span::call_site()),
+                Ident::new(&f.to_case(Case::Pascal), Span::call_site()),
+                ty,
+            )
+        })
+        .collect::<Vec<_>>();
+    let ops = if args.enum_ops {
+        quote!(ops: Vec::new(),)
+    } else {
+        let ops = fields.iter().map(|(_, op, _, _, _)| op);
+        quote!(#(#ops: None,)*)
+    };
+    let version = args
+        .version
+        .as_ref()
+        .map(|_| quote!(version: Default::default(),));
+    let transact_ctx = quote!(#crdts_macro::TransactCtx<#actor>);
+    let setters = fields.iter().map(|(field, op, set_op, variant, ty)| {
+        let doc = format!(
+            "Set the op of `{field}`, built from its current state and the context derived for the transaction's actor."
+        );
+        let op_doc = format!(
+            "Set the op of `{field}` as is, e.g. when its actor type is not the transaction's."
+        );
+        let set = if args.enum_ops {
+            quote! {
+                let op = #field_op::#variant(op);
+                match self.op.ops.iter_mut().find(|op| matches!(op, #field_op::#variant(_))) {
+                    Some(set) => *set = op,
+                    None => self.op.ops.push(op),
+                }
+            }
+        } else {
+            quote!(self.op.#op = Some(op);)
+        };
+        quote! {
+            #[doc = #doc]
+            pub fn #field(
+                &mut self,
+                f: impl FnOnce(
+                    &#ty,
+                    <#ty as #transact_ctx>::Ctx,
+                ) -> <#ty as #crdts::CmRDT>::Op,
+            ) -> &mut Self
+            where
+                for<'__crdt> #ty: #transact_ctx,
+            {
+                let ctx = <#ty as #transact_ctx>::transact_ctx(&self.data.#field, self.actor.clone());
+                let op = f(&self.data.#field, ctx);
+                self.#set_op(op)
+            }
+
+            #[doc = #op_doc]
+            pub fn #set_op(&mut self, op: <#ty as #crdts::CmRDT>::Op) -> &mut Self {
+                #set
+                self
+            }
+        }
+    });
+
+    let tx_doc = format!(
+        "Collects field ops into one [`{op_name}`], see [`{name}::transact`]. Setting the op of a field twice keeps the last one."
+    );
+    quote! {
+        #[doc = #tx_doc]
+        pub struct #tx_name #tx_generics #where_clause {
+            data: &'a #name #ty_generics,
+            actor: #actor,
+            op: #op_name #ty_generics,
+        }
+
+        impl #tx_impl_generics #tx_name #tx_ty_generics #where_clause {
+            #(#setters)*
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Build a single op from the fields touched in `f`, stamped with
+            /// the next dot of `actor`. The op still has to be applied.
+            pub fn transact<'a>(
+                &'a self,
+                actor: #actor,
+                f: impl FnOnce(&mut #tx_name #tx_ty_generics),
+            ) -> #op_name #ty_generics {
+                let mut tx = #tx_name {
+                    data: self,
+                    actor: actor.clone(),
+                    op: #op_name {
+                        dot: self.v_clock.inc(actor),
+                        #ops
+                        #version
+                    },
+                };
+                f(&mut tx);
+                tx.op
+            }
+        }
+    }
+}
+
+/// `TransactCtx` handing the actor to the closure building the op of a field
+/// holding this struct.
+pub(crate) fn impl_transact_ctx(
+    name: &Ident,
+    generics: &Generics,
+    actor: &Type,
+    args: &Args,
+) -> TokenStream {
+    let crdts_macro = args.crdts_macro();
+    let mut generics = generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(#actor: Ord));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics #crdts_macro::TransactCtx<#actor> for #name #ty_generics #where_clause {
+            type Ctx = #actor;
+
+            fn transact_ctx(&self, actor: #actor) -> Self::Ctx {
+                actor
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::Generics;
+
+use crate::args::Args;
+
+/// `deserialize_migrating` on the struct, and on its op with `op_with`: both
+/// look at the `version` first and read data without one or with an older
+/// one as `migrate_from`, which is then upgraded. Data of a newer version is
+/// rejected.
+///
+/// The deserializer is cloned to read the data twice instead of buffering it
+/// like `#[serde(untagged)]` does, which loses e.g. the integer map keys of
+/// `VClock` in JSON.
+pub(crate) fn impl_migrate(name: &Ident, generics: &Generics, args: &Args) -> TokenStream {
+    let (Some(from), Some(with)) = (&args.migrate_from, &args.with) else {
+        return TokenStream::new();
+    };
+    let crdts = args.crdts();
+    let serde = args.serde();
+    let serde_crate = serde.to_token_stream().to_string();
+    let version = &args.version;
+    let op_name = Ident::new(&(name.to_string() + "CrdtOp"), Span::call_site());
+    let is_current = quote! {
+        #[derive(#serde::Deserialize)]
+        #[serde(crate = #serde_crate)]
+        struct Probe {
+            #[serde(default)]
+            version: Option<u32>,
+        }
+        let probe = <Probe as #serde::Deserialize>::deserialize(deserializer.clone())?;
+        let is_current = probe.version.is_some_and(|v| v >= #version);
+    };
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let state_doc = format!(
+        "Deserialize a state of this version, or of `{}` upgraded with `{}`.",
+        from.to_token_stream(),
+        with.to_token_stream()
+    );
+    let migrate_op = args.op_with.as_ref().map(|op_with| {
+        let op_doc = format!(
+            "Deserialize an op of this version, or of `{}` upgraded with `{}`.",
+            from.to_token_stream(),
+            op_with.to_token_stream()
+        );
+        quote! {
+            impl #impl_generics #op_name #ty_generics #where_clause {
+                #[doc = #op_doc]
+                pub fn deserialize_migrating<'de, D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: #serde::Deserializer<'de> + Clone,
+                    Self: #serde::Deserialize<'de>,
+                    <#from as #crdts::CmRDT>::Op: #serde::Deserialize<'de>,
+                {
+                    #is_current
+                    if is_current {
+                        <Self as #serde::Deserialize>::deserialize(deserializer)
+                    } else {
+                        <<#from as #crdts::CmRDT>::Op as #serde::Deserialize>::deserialize(deserializer)
+                            .map(#op_with)
+                    }
+                }
+            }
+        }
+    });
+
+    quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[doc = #state_doc]
+            pub fn deserialize_migrating<'de, D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: #serde::Deserializer<'de> + Clone,
+                Self: #serde::Deserialize<'de>,
+                #from: #serde::Deserialize<'de>,
+            {
+                #is_current
+                if is_current {
+                    <Self as #serde::Deserialize>::deserialize(deserializer)
+                } else {
+                    <#from as #serde::Deserialize>::deserialize(deserializer).map(#with)
+                }
+            }
+        }
+
+        #migrate_op
+    }
+}
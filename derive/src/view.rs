@@ -0,0 +1,78 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Generics, Type};
+
+use crate::args::Args;
+use crate::{field_ident, projection_bounds, serde_bound};
+
+/// `DataView` holding the `IntoView::View` of every field, and the
+/// `IntoView` impl building it.
+pub(crate) fn impl_view(
+    name: &Ident,
+    generics: &Generics,
+    fields: &[(String, Type)],
+    args: &Args,
+) -> TokenStream {
+    let crdts_macro = args.crdts_macro();
+    let view_name = Ident::new(&(name.to_string() + "View"), Span::call_site());
+    let view_derives = args.view_derives();
+
+    let fields = fields
+        .iter()
+        .filter(|(f, _)| f != "v_clock")
+        .map(|(f, ty)| (field_ident(f), ty))
+        .collect::<Vec<_>>();
+
+    let views = fields
+        .iter()
+        .map(|(_, ty)| quote!(<#ty as #crdts_macro::IntoView>::View))
+        .collect::<Vec<_>>();
+    let mut generics = generics.clone();
+    let predicates = &mut generics.make_where_clause().predicates;
+    for (_, ty) in &fields {
+        predicates.push(parse_quote!(#ty: #crdts_macro::IntoView));
+    }
+    projection_bounds(
+        &mut generics,
+        &views,
+        quote!(std::fmt::Debug + Clone + PartialEq),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let serde_bound =
+        (!args.no_serde).then(|| serde_bound(&generics, &views, &args.serde(), false));
+    let slots = fields
+        .iter()
+        .map(|(field, ty)| quote!(pub #field: <#ty as #crdts_macro::IntoView>::View,));
+    let views = fields
+        .iter()
+        .map(|(field, ty)| quote!(#field: <#ty as #crdts_macro::IntoView>::view(&self.#field),));
+
+    let view_doc = format!("The fields of a [`{name}`] as plain values, see `{name}::view`.");
+    quote! {
+        #[doc = #view_doc]
+        #[allow(clippy::type_complexity)]
+        #view_derives
+        #serde_bound
+        pub struct #view_name #generics #where_clause {
+            #(#slots)*
+        }
+
+        impl #impl_generics #crdts_macro::IntoView for #name #ty_generics #where_clause {
+            type View = #view_name #ty_generics;
+
+            fn view(&self) -> Self::View {
+                #view_name {
+                    #(#views)*
+                }
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Read every field as a plain value.
+            pub fn view(&self) -> #view_name #ty_generics {
+                <Self as #crdts_macro::IntoView>::view(self)
+            }
+        }
+    }
+}
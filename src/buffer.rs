@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+use crdts::{DotRange, VClock};
+
+use crate::Causal;
+
+/// Holds ops that arrive ahead of their turn until they can be delivered.
+///
+/// An op is deliverable once its dot is the next one of its actor in the
+/// clock of the target, which is also what `validate_op` checks. Ops from
+/// the same actor are therefore released in the order they were created,
+/// ops that are already covered by the clock are dropped.
+pub struct CausalBuffer<T: Causal> {
+    pending: BTreeMap<T::Actor, BTreeMap<u64, T::Op>>,
+}
+
+impl<T: Causal> Default for CausalBuffer<T> {
+    fn default() -> Self {
+        Self {
+            pending: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Causal> CausalBuffer<T> {
+    /// Returns a new, empty buffer.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Queue an op. A second op with the same dot replaces the first one.
+    pub fn push(&mut self, op: T::Op) {
+        let dot = T::dot(&op);
+        self.pending
+            .entry(dot.actor.clone())
+            .or_default()
+            .insert(dot.counter, op);
+    }
+
+    /// Take the next op deliverable against `clock`, dropping queued ops the
+    /// clock has already seen.
+    pub fn pop_ready(&mut self, clock: &VClock<T::Actor>) -> Option<T::Op> {
+        let mut ready = None;
+        self.pending.retain(|actor, ops| {
+            let next = clock.get(actor) + 1;
+            while let Some(entry) = ops.first_entry() {
+                if *entry.key() >= next {
+                    break;
+                }
+                entry.remove();
+            }
+            if ready.is_none() {
+                ready = ops.remove(&next);
+            }
+            !ops.is_empty()
+        });
+        ready
+    }
+
+    /// Apply every op that is or becomes deliverable to `state`, returns the
+    /// number of applied ops. No observer is notified, call `pop_ready` and
+    /// `apply_observed` for that.
+    pub fn deliver(&mut self, state: &mut T) -> usize {
+        let mut applied = 0;
+        while let Some(op) = self.pop_ready(state.clock()) {
+            state.apply(op);
+            applied += 1;
+        }
+        applied
+    }
+
+    /// Number of queued ops.
+    pub fn len(&self) -> usize {
+        self.pending.values().map(BTreeMap::len).sum()
+    }
+
+    /// Returns `true` if no op is queued.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Number of queued ops per actor.
+    pub fn pending(&self) -> BTreeMap<T::Actor, usize> {
+        self.pending
+            .iter()
+            .map(|(actor, ops)| (actor.clone(), ops.len()))
+            .collect()
+    }
+
+    /// The dots that have to arrive before the queued ops of each actor can
+    /// be delivered against `clock`, one range per gap.
+    pub fn missing(&self, clock: &VClock<T::Actor>) -> Vec<DotRange<T::Actor>> {
+        let mut missing = Vec::new();
+        for (actor, ops) in &self.pending {
+            let mut next = clock.get(actor) + 1;
+            for &counter in ops.range(next..).map(|(counter, _)| counter) {
+                if counter > next {
+                    missing.push(DotRange {
+                        actor: actor.clone(),
+                        counter_range: next..counter,
+                    });
+                }
+                next = counter + 1;
+            }
+        }
+        missing
+    }
+}
@@ -0,0 +1,83 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use crdts::{CmRDT, CvRDT, Dot, ResetRemove, VClock};
+use serde::{Deserialize, Serialize};
+
+use crate::Causal;
+
+/// A CRDT that can ship only the part of its state a peer has not seen.
+/// Implemented by `#[crdt(.., delta)]` structs, and with the whole state as
+/// the delta by every other `#[crdt]` struct that is not `embedded`.
+pub trait DeltaCrdt: Causal {
+    /// The changes between two states, `DataDelta` for a struct `Data`.
+    type Delta;
+
+    /// Everything a replica at `clock` has not observed yet. The granularity
+    /// is the field: a field with a single unseen dot is sent whole, with all
+    /// of its state, not just the part that changed.
+    fn delta_since(&self, clock: &VClock<Self::Actor>) -> Self::Delta;
+
+    /// Merge a delta produced by another replica's `delta_since`.
+    fn merge_delta(&mut self, delta: Self::Delta);
+}
+
+/// The dots that touched each field, keyed by field name. `delta` structs keep
+/// them in `field_clocks` to find the fields a peer is missing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldClocks<A: Ord> {
+    clocks: BTreeMap<String, VClock<A>>,
+}
+
+impl<A: Ord> Default for FieldClocks<A> {
+    fn default() -> Self {
+        Self {
+            clocks: BTreeMap::new(),
+        }
+    }
+}
+
+impl<A: Ord + Clone + Debug> FieldClocks<A> {
+    /// The clock of `field`, if it has been touched at all.
+    pub fn get(&self, field: &str) -> Option<&VClock<A>> {
+        self.clocks.get(field)
+    }
+
+    /// Record that the op with `dot` touched `field`.
+    pub fn apply(&mut self, field: &str, dot: Dot<A>) {
+        self.clocks.entry(field.to_string()).or_default().apply(dot);
+    }
+
+    /// Merge the clock of a single field.
+    pub fn merge_field(&mut self, field: &str, clock: VClock<A>) {
+        self.clocks
+            .entry(field.to_string())
+            .or_default()
+            .merge(clock);
+    }
+
+    /// Returns `true` if `field` has been touched by a dot `clock` has not
+    /// seen.
+    pub fn changed_since(&self, field: &str, clock: &VClock<A>) -> bool {
+        self.get(field).is_some_and(|c| {
+            !matches!(c.partial_cmp(clock), Some(Ordering::Less | Ordering::Equal))
+        })
+    }
+
+    /// Merge the clocks of every field.
+    pub fn merge(&mut self, other: Self) {
+        for (field, clock) in other.clocks {
+            self.clocks.entry(field).or_default().merge(clock);
+        }
+    }
+}
+
+impl<A: Ord + Clone + Debug> ResetRemove<A> for FieldClocks<A> {
+    fn reset_remove(&mut self, clock: &VClock<A>) {
+        for field_clock in self.clocks.values_mut() {
+            field_clock.reset_remove(clock);
+        }
+        self.clocks.retain(|_, c| !c.is_empty());
+    }
+}
@@ -0,0 +1,26 @@
+use std::error::Error;
+use std::fmt;
+
+/// Static description of a field of a `#[crdt]` struct, see `Data::FIELDS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FieldInfo {
+    /// The field name, e.g. `"a"`.
+    pub name: &'static str,
+    /// The field type as written, e.g. `"Orswot<String, u64>"`.
+    pub type_name: &'static str,
+    /// The CRDT type without its parameters, e.g. `"Orswot"`.
+    pub kind: &'static str,
+}
+
+/// A string that names no field of the struct, returned when parsing a
+/// generated `DataField`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownField(pub String);
+
+impl fmt::Display for UnknownField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown field `{}`", self.0)
+    }
+}
+
+impl Error for UnknownField {}
@@ -0,0 +1,156 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crdts::ctx::AddCtx;
+use crdts::{Actor, CmRDT, CvRDT, GCounter, GSet, MVReg, Map, Orswot, PNCounter, ResetRemove};
+use serde::{Deserialize, Serialize};
+
+/// A change to a CRDT that can be described without holding its state,
+/// turned into an op where the state lives. Implemented by
+/// `#[crdt(.., intents)]` structs and the `crdts` types below.
+pub trait HasIntent<A: Ord>: CmRDT {
+    /// The changes that can be requested.
+    type Intent;
+
+    /// Build the op carrying out `intent` as `actor`.
+    fn intent_op(&self, actor: A, intent: Self::Intent) -> Self::Op;
+
+    /// Like `intent_op`, under the context `ctx` of the `Map` holding `self`.
+    /// CRDTs counting their own dots ignore it.
+    fn intent_op_with_ctx(&self, intent: Self::Intent, ctx: AddCtx<A>) -> Self::Op {
+        self.intent_op(ctx.dot.actor, intent)
+    }
+}
+
+/// Intent for a `GCounter`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GCounterIntent {
+    Inc,
+    IncBy(u64),
+}
+
+impl<A: Actor + Debug> HasIntent<A> for GCounter<A> {
+    type Intent = GCounterIntent;
+
+    fn intent_op(&self, actor: A, intent: Self::Intent) -> Self::Op {
+        match intent {
+            GCounterIntent::Inc => self.inc(actor),
+            GCounterIntent::IncBy(steps) => self.inc_many(actor, steps),
+        }
+    }
+}
+
+/// Intent for a `PNCounter`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PNCounterIntent {
+    Inc,
+    Dec,
+    IncBy(u64),
+    DecBy(u64),
+}
+
+impl<A: Actor + Debug> HasIntent<A> for PNCounter<A> {
+    type Intent = PNCounterIntent;
+
+    fn intent_op(&self, actor: A, intent: Self::Intent) -> Self::Op {
+        match intent {
+            PNCounterIntent::Inc => self.inc(actor),
+            PNCounterIntent::Dec => self.dec(actor),
+            PNCounterIntent::IncBy(steps) => self.inc_many(actor, steps),
+            PNCounterIntent::DecBy(steps) => self.dec_many(actor, steps),
+        }
+    }
+}
+
+/// Intent for an `Orswot`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrswotIntent<M> {
+    Add(M),
+    Rm(M),
+}
+
+impl<M: Hash + Eq + Clone + Debug, A: Actor + Debug> HasIntent<A> for Orswot<M, A> {
+    type Intent = OrswotIntent<M>;
+
+    fn intent_op(&self, actor: A, intent: Self::Intent) -> Self::Op {
+        self.intent_op_with_ctx(intent, self.read_ctx().derive_add_ctx(actor))
+    }
+
+    fn intent_op_with_ctx(&self, intent: Self::Intent, ctx: AddCtx<A>) -> Self::Op {
+        match intent {
+            OrswotIntent::Add(member) => self.add(member, ctx),
+            OrswotIntent::Rm(member) => {
+                let rm_ctx = self.contains(&member).derive_rm_ctx();
+                self.rm(member, rm_ctx)
+            }
+        }
+    }
+}
+
+/// Intent for a `GSet`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GSetIntent<T> {
+    Insert(T),
+}
+
+impl<T: Ord, A: Ord> HasIntent<A> for GSet<T> {
+    type Intent = GSetIntent<T>;
+
+    fn intent_op(&self, _actor: A, intent: Self::Intent) -> Self::Op {
+        match intent {
+            GSetIntent::Insert(element) => element,
+        }
+    }
+}
+
+/// Intent for an `MVReg`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MVRegIntent<V> {
+    Write(V),
+}
+
+impl<V: Clone + Debug, A: Actor + Debug> HasIntent<A> for MVReg<V, A> {
+    type Intent = MVRegIntent<V>;
+
+    fn intent_op(&self, actor: A, intent: Self::Intent) -> Self::Op {
+        self.intent_op_with_ctx(intent, self.read_ctx().derive_add_ctx(actor))
+    }
+
+    fn intent_op_with_ctx(&self, intent: Self::Intent, ctx: AddCtx<A>) -> Self::Op {
+        match intent {
+            MVRegIntent::Write(val) => self.write(val, ctx),
+        }
+    }
+}
+
+/// Intent for a `Map`, updating a value with its own intent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MapIntent<K, I> {
+    Update { key: K, intent: I },
+    Rm(K),
+}
+
+impl<K, V, A> HasIntent<A> for Map<K, V, A>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Default + ResetRemove<A> + CmRDT + CvRDT + HasIntent<A> + Debug,
+    A: Actor + Debug,
+{
+    type Intent = MapIntent<K, V::Intent>;
+
+    fn intent_op(&self, actor: A, intent: Self::Intent) -> Self::Op {
+        self.intent_op_with_ctx(intent, self.read_ctx().derive_add_ctx(actor))
+    }
+
+    fn intent_op_with_ctx(&self, intent: Self::Intent, ctx: AddCtx<A>) -> Self::Op {
+        match intent {
+            MapIntent::Update { key, intent } => {
+                self.update(key, ctx, |val, ctx| val.intent_op_with_ctx(intent, ctx))
+            }
+            MapIntent::Rm(key) => {
+                let rm_ctx = self.get(&key).derive_rm_ctx();
+                self.rm(key, rm_ctx)
+            }
+        }
+    }
+}
@@ -1,2 +1,54 @@
+mod buffer;
+mod delta;
+mod field;
+mod intent;
+pub mod oplog;
+mod read_ctx;
+pub mod snapshot;
+pub mod sync;
+mod version;
+mod view;
+
+use std::fmt::Debug;
+
+use crdts::{Actor, CmRDT, Dot, VClock};
+
+pub use crdts;
 pub use crdts_macro_derive::{crdt, CRDT};
 pub use serde::{self, Deserialize, Serialize};
+
+pub use crate::buffer::CausalBuffer;
+pub use crate::delta::{DeltaCrdt, FieldClocks};
+pub use crate::field::{FieldInfo, UnknownField};
+pub use crate::intent::{
+    GCounterIntent, GSetIntent, HasIntent, MVRegIntent, MapIntent, OrswotIntent, PNCounterIntent,
+};
+pub use crate::read_ctx::{DeriveCtx, HasReadCtx, TransactCtx};
+pub use crate::version::Version;
+pub use crate::view::IntoView;
+
+/// What the generated `try_apply` did with an op.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApplyOutcome<E> {
+    /// The op was valid and has been applied.
+    Applied,
+    /// The op's dot has already been seen, nothing changed.
+    Duplicate,
+    /// The op did not carry any field op, only its dot was recorded.
+    Empty,
+    /// `validate_op` rejected the op, nothing changed.
+    Invalid(E),
+}
+
+/// A CRDT tracking causality with a single root clock. Implemented by every
+/// `#[crdt]` struct that is not `embedded`.
+pub trait Causal: CmRDT {
+    /// The actor type of the root clock.
+    type Actor: Actor + Debug;
+
+    /// The root clock, `v_clock` for `#[crdt]` structs.
+    fn clock(&self) -> &VClock<Self::Actor>;
+
+    /// The dot an op is applied under.
+    fn dot(op: &Self::Op) -> &Dot<Self::Actor>;
+}
@@ -0,0 +1,240 @@
+//! Persisting ops to rebuild a replica after a restart.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crdts::VClock;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::Causal;
+
+/// Where an [`OpLog`] keeps its ops.
+pub trait OpStore<Op> {
+    type Error;
+
+    /// Persist one more op.
+    fn append(&mut self, op: &Op) -> Result<(), Self::Error>;
+
+    /// Every persisted op, in the order they were appended.
+    fn load(&self) -> Result<Vec<Op>, Self::Error>;
+
+    /// Replace every persisted op with `ops`.
+    fn replace(&mut self, ops: &[Op]) -> Result<(), Self::Error>;
+
+    /// Clean up after an append that was cut short, called by
+    /// [`OpLog::open`] before loading. Does nothing by default.
+    fn repair(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// An [`OpStore`] keeping the ops in memory.
+#[derive(Debug, Clone)]
+pub struct MemoryStore<Op> {
+    ops: Vec<Op>,
+}
+
+impl<Op> Default for MemoryStore<Op> {
+    fn default() -> Self {
+        Self { ops: Vec::new() }
+    }
+}
+
+impl<Op: Clone> OpStore<Op> for MemoryStore<Op> {
+    type Error = std::convert::Infallible;
+
+    fn append(&mut self, op: &Op) -> Result<(), Self::Error> {
+        self.ops.push(op.clone());
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Vec<Op>, Self::Error> {
+        Ok(self.ops.clone())
+    }
+
+    fn replace(&mut self, ops: &[Op]) -> Result<(), Self::Error> {
+        self.ops = ops.to_vec();
+        Ok(())
+    }
+}
+
+/// An [`OpStore`] writing one JSON encoded op per line to a local file.
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    /// Use the file at `path`, which is created on the first append.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl<Op: Serialize + DeserializeOwned> OpStore<Op> for FileStore {
+    type Error = io::Error;
+
+    fn append(&mut self, op: &Op) -> Result<(), Self::Error> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(&to_line(op)?)?;
+        file.sync_data()
+    }
+
+    /// A last line that does not parse is what a crash during `append`
+    /// leaves behind and is skipped, see `repair`.
+    fn load(&self) -> Result<Vec<Op>, Self::Error> {
+        let buf = self.read()?;
+        let (lines, last) = split_last_line(&buf);
+        let mut ops = Vec::new();
+        for line in lines.lines() {
+            ops.push(serde_json::from_str(&line?)?);
+        }
+        if let Ok(op) = serde_json::from_slice(last) {
+            ops.push(op);
+        }
+        Ok(ops)
+    }
+
+    /// Writes the new file next to the old one and renames it over, so a
+    /// crash leaves either of them intact.
+    fn replace(&mut self, ops: &[Op]) -> Result<(), Self::Error> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let mut file = File::create(&tmp)?;
+        for op in ops {
+            file.write_all(&to_line(op)?)?;
+        }
+        file.sync_data()?;
+        fs::rename(tmp, &self.path)
+    }
+
+    /// Ends a last op that is only missing its newline and cuts off a last
+    /// line that does not parse, so the next append starts on a fresh line.
+    /// The file is left alone, and not opened for writing, if it is whole.
+    fn repair(&mut self) -> Result<(), Self::Error> {
+        let buf = self.read()?;
+        let (lines, last) = split_last_line(&buf);
+        if last.is_empty() {
+            return Ok(());
+        }
+        let mut file = OpenOptions::new().append(true).open(&self.path)?;
+        if serde_json::from_slice::<Op>(last).is_ok() {
+            file.write_all(b"\n")?;
+        } else {
+            file.set_len(lines.len() as u64)?;
+        }
+        file.sync_data()
+    }
+}
+
+impl FileStore {
+    /// The contents of the file, empty if it was not created yet.
+    fn read(&self) -> io::Result<Vec<u8>> {
+        match fs::read(&self.path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            read => read,
+        }
+    }
+}
+
+/// The newline terminated lines of `buf` and what follows the last newline.
+fn split_last_line(buf: &[u8]) -> (&[u8], &[u8]) {
+    let end = buf.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    buf.split_at(end)
+}
+
+fn to_line<Op: Serialize>(op: &Op) -> io::Result<Vec<u8>> {
+    let mut line = serde_json::to_vec(op)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+/// An append-only log of the ops of a `T`, backed by an [`OpStore`].
+pub struct OpLog<T: Causal, S> {
+    store: S,
+    ops: Vec<T::Op>,
+}
+
+impl<T: Causal, S: OpStore<T::Op>> OpLog<T, S> {
+    /// Open the log, reading the ops already in `store`.
+    pub fn open(mut store: S) -> Result<Self, S::Error> {
+        store.repair()?;
+        let ops = store.load()?;
+        Ok(Self { store, ops })
+    }
+
+    /// Persist `op`, which is kept as is even if its dot is already logged.
+    pub fn append(&mut self, op: T::Op) -> Result<(), S::Error> {
+        self.store.append(&op)?;
+        self.ops.push(op);
+        Ok(())
+    }
+
+    /// Every logged op, in the order they were appended.
+    pub fn ops(&self) -> &[T::Op] {
+        &self.ops
+    }
+
+    /// The logged ops whose dot is not covered by `clock`.
+    pub fn since<'a>(
+        &'a self,
+        clock: &'a VClock<T::Actor>,
+    ) -> impl Iterator<Item = &'a T::Op> + 'a {
+        self.ops.iter().filter(move |op| {
+            let dot = T::dot(op);
+            clock.get(&dot.actor) < dot.counter
+        })
+    }
+
+    /// Drop the ops covered by `clock`, typically the clock of a persisted
+    /// snapshot. Returns the number of dropped ops.
+    pub fn compact(&mut self, clock: &VClock<T::Actor>) -> Result<usize, S::Error>
+    where
+        T::Op: Clone,
+    {
+        let ops = self.since(clock).cloned().collect::<Vec<_>>();
+        self.store.replace(&ops)?;
+        let dropped = self.ops.len() - ops.len();
+        self.ops = ops;
+        Ok(dropped)
+    }
+
+    /// Number of logged ops.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns `true` if nothing has been logged yet.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Rebuild the state by applying every logged op to `T::default()`.
+    pub fn replay(&self) -> T
+    where
+        T: Default,
+        T::Op: Clone,
+    {
+        self.restore(T::default())
+    }
+
+    /// Bring a restored snapshot up to date by applying the logged ops its
+    /// clock has not seen. No observer is notified.
+    pub fn restore(&self, mut state: T) -> T
+    where
+        T::Op: Clone,
+    {
+        let ops = self.since(state.clock()).cloned().collect::<Vec<_>>();
+        for op in ops {
+            state.apply(op);
+        }
+        state
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crdts::ctx::{AddCtx, ReadCtx, RmCtx};
+use crdts::{Actor, CmRDT, CvRDT, GCounter, GList, GSet, LWWReg, List, MVReg, Map, Orswot};
+use crdts::{PNCounter, ResetRemove, VClock};
+
+/// The causal context needed to build ops for a CRDT. Implemented by
+/// `#[crdt(.., read_ctx)]` structs and the `crdts` types below; CRDTs whose
+/// ops need no context use `()`.
+pub trait HasReadCtx<A: Ord> {
+    /// `ReadCtx<(), A>` or `()`.
+    type ReadCtx;
+
+    /// Read the current context.
+    fn read_ctx(&self) -> Self::ReadCtx;
+}
+
+impl<M: Hash + Eq + Clone, A: Actor> HasReadCtx<A> for Orswot<M, A> {
+    type ReadCtx = ReadCtx<(), A>;
+
+    fn read_ctx(&self) -> Self::ReadCtx {
+        Orswot::read_ctx(self)
+    }
+}
+
+impl<V: Clone, A: Actor + Debug> HasReadCtx<A> for MVReg<V, A> {
+    type ReadCtx = ReadCtx<(), A>;
+
+    fn read_ctx(&self) -> Self::ReadCtx {
+        MVReg::read_ctx(self)
+    }
+}
+
+impl<K, V, A> HasReadCtx<A> for Map<K, V, A>
+where
+    K: Ord,
+    V: Clone + Default + ResetRemove<A> + CmRDT + CvRDT,
+    A: Actor + Debug,
+{
+    type ReadCtx = ReadCtx<(), A>;
+
+    fn read_ctx(&self) -> Self::ReadCtx {
+        Map::read_ctx(self)
+    }
+}
+
+macro_rules! no_read_ctx {
+    ($($ty:ty => [$($param:tt)*]),* $(,)?) => {
+        $(
+            impl<A: Ord, $($param)*> HasReadCtx<A> for $ty {
+                type ReadCtx = ();
+
+                fn read_ctx(&self) -> Self::ReadCtx {}
+            }
+        )*
+    };
+}
+
+no_read_ctx! {
+    GCounter<B> => [B: Ord],
+    PNCounter<B> => [B: Ord],
+    GSet<T> => [T: Ord],
+    LWWReg<V, M> => [V, M],
+    List<T, B> => [T, B: Ord],
+    GList<T> => [T: Ord],
+    VClock<B> => [B: Ord],
+}
+
+/// Contexts for one op at a time from a `ReadCtx` that is kept around, e.g.
+/// a field of a generated `DataReadCtx`.
+pub trait DeriveCtx<A: Ord> {
+    /// Like `ReadCtx::derive_add_ctx`, without consuming the read context.
+    fn add_ctx(&self, actor: A) -> AddCtx<A>;
+
+    /// Like `ReadCtx::derive_rm_ctx`, without consuming the read context.
+    fn rm_ctx(&self) -> RmCtx<A>;
+}
+
+impl<V, A: Ord + Clone + Debug> DeriveCtx<A> for ReadCtx<V, A> {
+    fn add_ctx(&self, actor: A) -> AddCtx<A> {
+        let mut clock = self.add_clock.clone();
+        let dot = clock.inc(actor);
+        clock.apply(dot.clone());
+        AddCtx { clock, dot }
+    }
+
+    fn rm_ctx(&self) -> RmCtx<A> {
+        RmCtx {
+            clock: self.rm_clock.clone(),
+        }
+    }
+}
+
+/// The context `Data::transact` hands to the closure building the op of a
+/// field, derived for the transaction's actor: an `AddCtx` for CRDTs that
+/// track causality, the actor itself for the others.
+pub trait TransactCtx<A: Ord> {
+    /// `AddCtx<A>` or `A`.
+    type Ctx;
+
+    /// Derive the context for `actor`.
+    fn transact_ctx(&self, actor: A) -> Self::Ctx;
+}
+
+macro_rules! add_transact_ctx {
+    ($($ty:ty => [$($param:tt)*]),* $(,)?) => {
+        $(
+            impl<A: Actor + Debug, $($param)*> TransactCtx<A> for $ty {
+                type Ctx = AddCtx<A>;
+
+                fn transact_ctx(&self, actor: A) -> Self::Ctx {
+                    HasReadCtx::read_ctx(self).derive_add_ctx(actor)
+                }
+            }
+        )*
+    };
+}
+
+add_transact_ctx! {
+    Orswot<M, A> => [M: Hash + Eq + Clone],
+    MVReg<V, A> => [V: Clone],
+    Map<K, V, A> => [K: Ord, V: Clone + Default + ResetRemove<A> + CmRDT + CvRDT],
+}
+
+macro_rules! actor_transact_ctx {
+    ($($ty:ty => [$($param:tt)*]),* $(,)?) => {
+        $(
+            impl<A: Ord, $($param)*> TransactCtx<A> for $ty {
+                type Ctx = A;
+
+                fn transact_ctx(&self, actor: A) -> Self::Ctx {
+                    actor
+                }
+            }
+        )*
+    };
+}
+
+actor_transact_ctx! {
+    GCounter<A> => [],
+    PNCounter<A> => [],
+    GSet<T> => [T: Ord],
+    LWWReg<V, M> => [V, M],
+    List<T, A> => [T],
+    GList<T> => [T: Ord],
+}
@@ -0,0 +1,66 @@
+//! Persisted snapshots of a whole state, see [`OpLog::compact`] to drop the
+//! ops a snapshot covers.
+//!
+//! A snapshot is the JSON encoded state behind a line holding its FNV-1a
+//! checksum, so a truncated or otherwise corrupt file is detected on load.
+//!
+//! [`OpLog::compact`]: crate::oplog::OpLog::compact
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Encode `state` with its checksum.
+pub fn encode<T: Serialize>(state: &T) -> io::Result<Vec<u8>> {
+    let json = serde_json::to_vec(state)?;
+    let mut bytes = format!("{:016x}\n", checksum(&json)).into_bytes();
+    bytes.extend(json);
+    Ok(bytes)
+}
+
+/// Decode a state written by [`encode`], failing with
+/// [`io::ErrorKind::InvalidData`] if the checksum does not match.
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> io::Result<T> {
+    let corrupt = || io::Error::new(io::ErrorKind::InvalidData, "corrupt snapshot");
+    let newline = bytes.iter().position(|&b| b == b'\n').ok_or_else(corrupt)?;
+    let (header, json) = (&bytes[..newline], &bytes[newline + 1..]);
+    let expected = std::str::from_utf8(header)
+        .ok()
+        .and_then(|h| u64::from_str_radix(h, 16).ok())
+        .ok_or_else(corrupt)?;
+    if checksum(json) != expected {
+        return Err(corrupt());
+    }
+    Ok(serde_json::from_slice(json)?)
+}
+
+/// Write a snapshot of `state` to `path`, replacing the previous one only
+/// once the new one is complete.
+pub fn save<T: Serialize>(path: impl AsRef<Path>, state: &T) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(&encode(state)?)?;
+    file.sync_data()?;
+    fs::rename(tmp, path)
+}
+
+/// Read the snapshot at `path`, `None` if there is none yet.
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> io::Result<Option<T>> {
+    match fs::read(path) {
+        Ok(bytes) => decode(&bytes).map(Some),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// 64-bit FNV-1a.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
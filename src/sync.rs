@@ -0,0 +1,225 @@
+//! Anti-entropy between two replicas of a `#[crdt]` struct. Structs without
+//! `delta` send their whole state where a delta would go.
+//!
+//! Replicas greet each other with their clock, answer with the delta the
+//! other side is missing and broadcast their own ops as they make them. Ops
+//! that arrive out of order wait in a [`CausalBuffer`] and make the replica
+//! ask for the gap.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crdts::{CmRDT, CvRDT, VClock};
+use serde::{Deserialize, Serialize};
+
+use crate::{Causal, CausalBuffer, DeltaCrdt};
+
+/// A message of the sync protocol.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Message<A: Ord, Op, Delta> {
+    /// The clock of the sender, sent to start a sync.
+    Hello { clock: VClock<A> },
+    /// The sender is missing whatever is not covered by `clock`.
+    Need { clock: VClock<A> },
+    /// Ops made by or delivered to the sender.
+    Ops(Vec<Op>),
+    /// The changes the receiver asked for or did not have.
+    Delta(Delta),
+    /// The clock of the sender after merging a delta.
+    Ack { clock: VClock<A> },
+}
+
+/// The [`Message`] type exchanged by replicas of `T`.
+pub type SyncMessage<T> = Message<<T as Causal>::Actor, <T as CmRDT>::Op, <T as DeltaCrdt>::Delta>;
+
+/// A connection to a single peer.
+pub trait Transport<M> {
+    type Error;
+
+    /// Send a message to the peer.
+    fn send(&mut self, msg: M) -> Result<(), Self::Error>;
+
+    /// The next message from the peer, `None` if there is none right now.
+    fn recv(&mut self) -> Result<Option<M>, Self::Error>;
+}
+
+/// An in-memory [`Transport`], for tests. Messages sent while the link is
+/// partitioned are lost.
+pub struct MemoryTransport<M> {
+    outbox: Arc<Mutex<VecDeque<M>>>,
+    inbox: Arc<Mutex<VecDeque<M>>>,
+    partitioned: Arc<AtomicBool>,
+}
+
+impl<M> MemoryTransport<M> {
+    /// Both ends of a new link.
+    pub fn pair() -> (Self, Self) {
+        let a = Arc::new(Mutex::new(VecDeque::new()));
+        let b = Arc::new(Mutex::new(VecDeque::new()));
+        let partitioned = Arc::new(AtomicBool::new(false));
+        (
+            Self {
+                outbox: a.clone(),
+                inbox: b.clone(),
+                partitioned: partitioned.clone(),
+            },
+            Self {
+                outbox: b,
+                inbox: a,
+                partitioned,
+            },
+        )
+    }
+
+    /// Cut the link in both directions, dropping messages in flight.
+    pub fn partition(&self) {
+        self.partitioned.store(true, Ordering::SeqCst);
+        self.outbox.lock().unwrap().clear();
+        self.inbox.lock().unwrap().clear();
+    }
+
+    /// Restore the link.
+    pub fn heal(&self) {
+        self.partitioned.store(false, Ordering::SeqCst);
+    }
+}
+
+impl<M> Transport<M> for MemoryTransport<M> {
+    type Error = Infallible;
+
+    fn send(&mut self, msg: M) -> Result<(), Self::Error> {
+        if !self.partitioned.load(Ordering::SeqCst) {
+            self.outbox.lock().unwrap().push_back(msg);
+        }
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<Option<M>, Self::Error> {
+        Ok(self.inbox.lock().unwrap().pop_front())
+    }
+}
+
+/// The local state of a replica synced with one peer over `R`.
+pub struct Replica<T: DeltaCrdt, R> {
+    state: T,
+    transport: R,
+    buffer: CausalBuffer<T>,
+    peer_clock: VClock<T::Actor>,
+}
+
+impl<T, R> Replica<T, R>
+where
+    T: DeltaCrdt,
+    T::Op: Clone,
+    R: Transport<SyncMessage<T>>,
+{
+    /// Start from `state`, talking to the peer over `transport`.
+    pub fn new(state: T, transport: R) -> Self {
+        Self {
+            state,
+            transport,
+            buffer: CausalBuffer::new(),
+            peer_clock: VClock::new(),
+        }
+    }
+
+    /// The current state.
+    pub fn state(&self) -> &T {
+        &self.state
+    }
+
+    /// The transport to the peer.
+    pub fn transport(&self) -> &R {
+        &self.transport
+    }
+
+    /// The last clock the peer reported.
+    pub fn peer_clock(&self) -> &VClock<T::Actor> {
+        &self.peer_clock
+    }
+
+    /// Returns `true` if the peer reported having seen everything this
+    /// replica has.
+    pub fn is_synced(&self) -> bool {
+        seen(self.state.clock(), &self.peer_clock)
+    }
+
+    /// Apply a local op and send it to the peer.
+    pub fn apply(&mut self, op: T::Op) -> Result<(), R::Error> {
+        self.state.apply(op.clone());
+        self.transport.send(Message::Ops(vec![op]))
+    }
+
+    /// Start a sync by sending the local clock.
+    pub fn hello(&mut self) -> Result<(), R::Error> {
+        let clock = self.state.clock().clone();
+        self.transport.send(Message::Hello { clock })
+    }
+
+    /// Handle every received message, returns how many there were.
+    pub fn poll(&mut self) -> Result<usize, R::Error> {
+        let mut handled = 0;
+        while let Some(msg) = self.transport.recv()? {
+            self.handle(msg)?;
+            handled += 1;
+        }
+        Ok(handled)
+    }
+
+    fn handle(&mut self, msg: SyncMessage<T>) -> Result<(), R::Error> {
+        match msg {
+            Message::Hello { clock } => {
+                if !seen(self.state.clock(), &clock) {
+                    self.send_delta(&clock)?;
+                }
+                if !seen(&clock, self.state.clock()) {
+                    self.send_need()?;
+                }
+                self.peer_clock.merge(clock);
+            }
+            Message::Need { clock } => {
+                self.send_delta(&clock)?;
+                self.peer_clock.merge(clock);
+            }
+            Message::Ops(ops) => {
+                for op in ops {
+                    self.peer_clock.apply(T::dot(&op).clone());
+                    self.buffer.push(op);
+                }
+                self.buffer.deliver(&mut self.state);
+                if !self.buffer.missing(self.state.clock()).is_empty() {
+                    self.send_need()?;
+                }
+            }
+            Message::Delta(delta) => {
+                self.state.merge_delta(delta);
+                self.buffer.deliver(&mut self.state);
+                let clock = self.state.clock().clone();
+                self.transport.send(Message::Ack { clock })?;
+            }
+            Message::Ack { clock } => self.peer_clock.merge(clock),
+        }
+        Ok(())
+    }
+
+    fn send_delta(&mut self, clock: &VClock<T::Actor>) -> Result<(), R::Error> {
+        let delta = self.state.delta_since(clock);
+        self.transport.send(Message::Delta(delta))
+    }
+
+    fn send_need(&mut self) -> Result<(), R::Error> {
+        let clock = self.state.clock().clone();
+        self.transport.send(Message::Need { clock })
+    }
+}
+
+/// Returns `true` if every dot of `clock` is covered by `by`.
+fn seen<A: Ord>(clock: &VClock<A>, by: &VClock<A>) -> bool {
+    matches!(
+        clock.partial_cmp(by),
+        Some(cmp::Ordering::Less | cmp::Ordering::Equal)
+    )
+}
@@ -0,0 +1,52 @@
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// The schema version `N` of a `#[crdt(.., version = N)]` struct and its ops.
+/// Serialized as the number itself, anything else fails to deserialize.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version<const N: u32>;
+
+impl<const N: u32> Version<N> {
+    /// The version as a number.
+    pub const VERSION: u32 = N;
+}
+
+impl<const N: u32> Serialize for Version<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(N)
+    }
+}
+
+impl<'de, const N: u32> Deserialize<'de> for Version<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct VersionVisitor<const N: u32>;
+
+        impl<const N: u32> Visitor<'_> for VersionVisitor<N> {
+            type Value = Version<N>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "version {N}")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                if v == u64::from(N) {
+                    Ok(Version)
+                } else {
+                    Err(E::invalid_value(de::Unexpected::Unsigned(v), &self))
+                }
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                if v == i64::from(N) {
+                    Ok(Version)
+                } else {
+                    Err(E::invalid_value(de::Unexpected::Signed(v), &self))
+                }
+            }
+        }
+
+        deserializer.deserialize_u32(VersionVisitor)
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crdts::{Actor, ResetRemove, VClock};
+use crdts::{CmRDT, CvRDT, GCounter, GList, GSet, LWWReg, List, MVReg, Map, Orswot, PNCounter};
+
+/// The value of a CRDT as an ordinary Rust value, without causal metadata.
+/// Implemented by `#[crdt(.., view)]` structs and the `crdts` types below.
+pub trait IntoView {
+    /// The plain value.
+    type View;
+
+    /// Read the plain value.
+    fn view(&self) -> Self::View;
+}
+
+impl<M: Hash + Eq + Clone, A: Actor> IntoView for Orswot<M, A> {
+    type View = HashSet<M>;
+
+    fn view(&self) -> Self::View {
+        self.read().val
+    }
+}
+
+impl<T: Ord + Clone> IntoView for GSet<T> {
+    type View = BTreeSet<T>;
+
+    fn view(&self) -> Self::View {
+        self.read()
+    }
+}
+
+/// Saturates at `u64::MAX`.
+impl<A: Ord + Clone> IntoView for GCounter<A> {
+    type View = u64;
+
+    fn view(&self) -> Self::View {
+        u64::try_from(self.read()).unwrap_or(u64::MAX)
+    }
+}
+
+/// Saturates at `i64::MIN` and `i64::MAX`.
+impl<A: Ord + Clone> IntoView for PNCounter<A> {
+    type View = i64;
+
+    fn view(&self) -> Self::View {
+        let value = self.read();
+        let negative = value < Default::default();
+        i64::try_from(value).unwrap_or(if negative { i64::MIN } else { i64::MAX })
+    }
+}
+
+impl<V: Clone, M> IntoView for LWWReg<V, M> {
+    type View = V;
+
+    fn view(&self) -> Self::View {
+        self.val.clone()
+    }
+}
+
+/// The concurrently written values.
+impl<V: Clone, A: Actor + Debug> IntoView for MVReg<V, A> {
+    type View = Vec<V>;
+
+    fn view(&self) -> Self::View {
+        self.read().val
+    }
+}
+
+impl<K, V, A> IntoView for Map<K, V, A>
+where
+    K: Ord + Clone,
+    V: IntoView + Clone + Default + ResetRemove<A> + CmRDT + CvRDT,
+    A: Actor + Debug,
+{
+    type View = BTreeMap<K, V::View>;
+
+    fn view(&self) -> Self::View {
+        self.iter()
+            .map(|entry| {
+                let (key, value) = entry.val;
+                (key.clone(), value.view())
+            })
+            .collect()
+    }
+}
+
+impl<T: Clone, A: Ord + Clone> IntoView for List<T, A> {
+    type View = Vec<T>;
+
+    fn view(&self) -> Self::View {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: Ord + Clone> IntoView for GList<T> {
+    type View = Vec<T>;
+
+    fn view(&self) -> Self::View {
+        self.read::<Vec<&T>>().into_iter().cloned().collect()
+    }
+}
+
+/// The counter of each actor.
+impl<A: Ord + Clone> IntoView for VClock<A> {
+    type View = BTreeMap<A, u64>;
+
+    fn view(&self) -> Self::View {
+        self.dots.clone()
+    }
+}
@@ -0,0 +1,107 @@
+use crdts::{CmRDT, DotRange, GCounter};
+use crdts_macro::{crdt, Causal, CausalBuffer};
+
+#[crdt(u64)]
+pub struct Data {
+    a: GCounter<u64>,
+}
+
+fn ops(actor: u64, n: u64) -> Vec<DataCrdtOp> {
+    let mut data = Data::default();
+    (0..n)
+        .map(|_| {
+            let op = data.transact(actor, |tx| {
+                tx.a(|a, actor| a.inc(actor));
+            });
+            data.apply(op.clone());
+            op
+        })
+        .collect()
+}
+
+#[test]
+fn delivers_in_order() {
+    let ops1 = ops(1, 3);
+    let ops2 = ops(2, 2);
+    let mut data = Data::default();
+    let mut buffer = CausalBuffer::<Data>::new();
+
+    buffer.push(ops1[2].clone());
+    buffer.push(ops1[1].clone());
+    buffer.push(ops2[1].clone());
+    assert_eq!(buffer.deliver(&mut data), 0);
+    assert_eq!(buffer.len(), 3);
+    assert_eq!(buffer.pending(), [(1, 2), (2, 1)].into());
+    assert_eq!(
+        buffer.missing(data.clock()),
+        vec![
+            DotRange {
+                actor: 1,
+                counter_range: 1..2
+            },
+            DotRange {
+                actor: 2,
+                counter_range: 1..2
+            },
+        ]
+    );
+
+    buffer.push(ops1[0].clone());
+    assert_eq!(buffer.deliver(&mut data), 3);
+    assert_eq!(data.a.read(), 3u8.into());
+    assert_eq!(buffer.pending(), [(2, 1)].into());
+
+    buffer.push(ops2[0].clone());
+    assert_eq!(buffer.deliver(&mut data), 2);
+    assert_eq!(data.a.read(), 5u8.into());
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn drops_seen_ops() {
+    let ops = ops(1, 2);
+    let mut data = Data::default();
+    data.apply(ops[0].clone());
+    data.apply(ops[1].clone());
+
+    let mut buffer = CausalBuffer::<Data>::new();
+    buffer.push(ops[0].clone());
+    buffer.push(ops[1].clone());
+    assert!(buffer.missing(data.clock()).is_empty());
+    assert_eq!(buffer.pop_ready(data.clock()), None);
+    assert!(buffer.is_empty());
+    assert_eq!(data.a.read(), 2u8.into());
+}
+
+#[test]
+fn reports_every_gap() {
+    let ops = ops(1, 6);
+    let mut data = Data::default();
+    let mut buffer = CausalBuffer::<Data>::new();
+    buffer.push(ops[2].clone());
+    buffer.push(ops[5].clone());
+    assert_eq!(
+        buffer.missing(data.clock()),
+        vec![
+            DotRange {
+                actor: 1,
+                counter_range: 1..3
+            },
+            DotRange {
+                actor: 1,
+                counter_range: 4..6
+            },
+        ]
+    );
+
+    buffer.push(ops[0].clone());
+    buffer.push(ops[1].clone());
+    assert_eq!(buffer.deliver(&mut data), 3);
+    assert_eq!(
+        buffer.missing(data.clock()),
+        vec![DotRange {
+            actor: 1,
+            counter_range: 4..6
+        }]
+    );
+}
@@ -0,0 +1,49 @@
+use crdts::{CmRDT, GCounter, Orswot};
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct Data {
+    a: Orswot<String, u64>,
+    b: GCounter<u64>,
+    c: GCounter<u64>,
+}
+
+#[test]
+fn apply_changes() {
+    let mut data = Data::default();
+    let op = data.transact(1, |tx| {
+        tx.a(|a, ctx| a.add("x".to_string(), ctx));
+        tx.c(|c, actor| c.inc(actor));
+    });
+    let changes = data.apply_with_changes(op.clone());
+    assert_eq!(
+        changes,
+        DataChanges {
+            a: true,
+            b: false,
+            c: true
+        }
+    );
+    assert!(data.apply_with_changes(op).is_empty());
+    assert!(data.apply_with_changes(data.transact(1, |_| {})).is_empty());
+}
+
+#[test]
+fn merge_changes() {
+    let mut r1 = Data::default();
+    let op = r1.transact(1, |tx| {
+        tx.a(|a, ctx| a.add("x".to_string(), ctx));
+    });
+    r1.apply(op);
+    let mut r2 = r1.clone();
+    let op = r2.transact(2, |tx| {
+        tx.b(|b, actor| b.inc(actor));
+    });
+    r2.apply(op);
+
+    // r1 has nothing r2 lacks, only r1 changes
+    assert!(r2.merge_with_changes(r1.clone()).is_empty());
+    let changes = r1.merge_with_changes(r2.clone());
+    assert!(!changes.a && changes.b && !changes.c);
+    assert_eq!(r1, r2);
+}
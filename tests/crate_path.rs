@@ -0,0 +1,66 @@
+mod facade {
+    pub use crdts_macro::{self, crdts, serde};
+}
+
+mod attr {
+    use crdts_macro::crdt;
+
+    use crate::facade::crdts::GCounter;
+
+    #[crdt(u64, crate = "crate::facade::crdts", serde = "crate::facade::serde")]
+    pub struct Data {
+        pub a: GCounter<u64>,
+    }
+}
+
+mod derive {
+    use crdts_macro::CRDT;
+
+    use crate::facade::crdts::{GCounter, VClock};
+
+    #[derive(Default, CRDT)]
+    #[crdt(crate = "crate::facade::crdts", serde = "crate::facade::serde")]
+    pub struct Data {
+        pub a: GCounter<u64>,
+        pub v_clock: VClock<u64>,
+    }
+}
+
+mod reexport {
+    use crdts_macro::crdt;
+
+    use crate::facade::crdts::GCounter;
+
+    #[crdt(u64, crdts_macro = "crate::facade::crdts_macro")]
+    pub struct Data {
+        pub a: GCounter<u64>,
+    }
+}
+
+#[test]
+fn generated_code_uses_the_given_paths() {
+    use facade::crdts::{CmRDT, Dot};
+
+    let mut data = attr::Data::default();
+    data.apply(attr::DataCrdtOp {
+        dot: Dot::new(1, 1),
+        a_op: Some(data.a.inc(1)),
+    });
+    assert_eq!(data.a.read(), 1u8.into());
+
+    let mut data = derive::Data::default();
+    data.apply(derive::DataCrdtOp {
+        dot: data.v_clock.inc(1),
+        a_op: Some(data.a.inc(1)),
+    });
+    assert_eq!(data.v_clock.get(&1), 1);
+
+    let mut data = reexport::Data::default();
+    let op = data.transact(1, |tx| {
+        tx.a(|a, actor| a.inc(actor));
+    });
+    assert_eq!(
+        data.try_apply(op),
+        facade::crdts_macro::ApplyOutcome::Applied
+    );
+}
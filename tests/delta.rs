@@ -0,0 +1,83 @@
+use crdts::{CmRDT, CvRDT, GCounter, Orswot, VClock};
+use crdts_macro::{crdt, DeltaCrdt, FieldClocks, CRDT};
+
+#[crdt(u64, delta)]
+pub struct Data {
+    a: Orswot<String, String>,
+    b: GCounter<u64>,
+    c: GCounter<u64>,
+}
+
+fn inc_b(data: &mut Data, actor: u64) {
+    let op = data.transact(actor, |tx| {
+        tx.b(|b, actor| b.inc(actor));
+    });
+    data.apply(op);
+}
+
+#[test]
+fn only_changed_fields() {
+    let mut r1 = Data::default();
+    let op = r1.transact(1, |tx| {
+        let ctx = r1.a.read_ctx().derive_add_ctx("x".to_string());
+        tx.set_a_op(r1.a.add("x".to_string(), ctx));
+        tx.c(|c, actor| c.inc(actor));
+    });
+    r1.apply(op);
+    let mut r2 = r1.clone();
+
+    inc_b(&mut r1, 1);
+    let delta = r1.delta_since(&r2.v_clock);
+    assert!(delta.a.is_none());
+    assert!(delta.b.is_some());
+    assert!(delta.c.is_none());
+    r2.merge_delta(delta);
+    assert_eq!(r1, r2);
+
+    assert!(r1.delta_since(&r2.v_clock).is_empty());
+}
+
+#[test]
+fn concurrent_deltas() {
+    let mut r1 = Data::default();
+    let mut r2 = Data::default();
+    inc_b(&mut r1, 1);
+    inc_b(&mut r2, 2);
+    let op = r2.transact(2, |tx| {
+        tx.c(|c, actor| c.inc(actor));
+    });
+    r2.apply(op);
+
+    let to_r2 = r1.delta_since(&r2.v_clock);
+    let to_r1 = r2.delta_since(&r1.v_clock);
+    assert!(to_r1.c.is_some());
+    r2.merge_delta(to_r2);
+    r1.merge_delta(to_r1);
+    assert_eq!(r1, r2);
+    assert_eq!(r1.b.read(), 2u8.into());
+
+    // a full merge keeps the field clocks too
+    let mut r3 = Data::default();
+    r3.merge(r1.clone());
+    assert!(r1.delta_since(&r3.v_clock).is_empty());
+    assert!(r3.delta_since(&VClock::new()).b.is_some());
+}
+
+#[derive(Default, Clone, CRDT)]
+#[crdt(u64, delta)]
+pub struct Derived {
+    a: GCounter<u64>,
+    v_clock: VClock<u64>,
+    field_clocks: FieldClocks<u64>,
+}
+
+#[test]
+fn derive() {
+    let mut data = Derived::default();
+    let op = data.transact(1, |tx| {
+        tx.a(|a, actor| a.inc(actor));
+    });
+    data.apply(op);
+    assert!(data.delta_since(&VClock::new()).a.is_some());
+    assert!(data.delta_since(&data.v_clock).is_empty());
+}
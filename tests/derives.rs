@@ -0,0 +1,55 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crdts::{CmRDT, Dot, GCounter};
+use crdts_macro::crdt;
+
+#[crdt(u64, no_default, no_debug, extra_derives(Hash), op_extra_derives(Hash))]
+pub struct Data {
+    a: GCounter<u64>,
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        let mut a = GCounter::new();
+        a.apply(a.inc(0));
+        Self {
+            a,
+            v_clock: Default::default(),
+        }
+    }
+}
+
+impl fmt::Debug for Data {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Data({})", self.a.read())
+    }
+}
+
+#[crdt(u64, no_serde)]
+pub struct Local {
+    a: GCounter<u64>,
+}
+
+#[test]
+fn hand_written_impls_are_kept() {
+    let data = Data::default();
+    assert_eq!(format!("{data:?}"), "Data(1)");
+    assert!(HashSet::from([data.clone()]).contains(&data));
+
+    let op = DataCrdtOp {
+        dot: Dot::new(1, 1),
+        a_op: Some(data.a.inc(1)),
+    };
+    assert!(HashSet::from([op.clone()]).contains(&op));
+}
+
+#[test]
+fn no_serde_still_applies_ops() {
+    let mut local = Local::default();
+    local.apply(LocalCrdtOp {
+        dot: Dot::new(1, 1),
+        a_op: Some(local.a.inc(1)),
+    });
+    assert_eq!(local.a.read(), 1u8.into());
+}
@@ -0,0 +1,66 @@
+use crdts::{CmRDT, CvRDT, GCounter, Orswot};
+use crdts_macro::crdt;
+
+#[crdt(u64, embedded)]
+pub struct Profile {
+    names: Orswot<String, u64>,
+    visits: GCounter<u64>,
+}
+
+#[crdt(u64)]
+pub struct Doc {
+    title: Orswot<String, u64>,
+    profile: Profile,
+}
+
+fn visit(doc: &Doc, actor: u64) -> DocCrdtOp {
+    DocCrdtOp {
+        dot: doc.v_clock.inc(actor),
+        title_op: None,
+        profile_op: Some(ProfileCrdtOp {
+            names_op: None,
+            visits_op: Some(doc.profile.visits.inc(actor)),
+        }),
+    }
+}
+
+#[test]
+fn root_clock_dedups_nested_ops() {
+    let mut doc = Doc::default();
+    let op = visit(&doc, 1);
+    assert_eq!(doc.validate_op(&op), Ok(()));
+    doc.apply(op.clone());
+    doc.apply(op);
+    assert_eq!(doc.profile.visits.read(), 1u8.into());
+
+    let op = visit(&doc, 1);
+    doc.apply(op);
+    assert_eq!(doc.profile.visits.read(), 2u8.into());
+}
+
+#[test]
+fn nested_fields_merge() {
+    let mut doc1 = Doc::default();
+    let mut doc2 = Doc::default();
+    doc1.apply(visit(&doc1, 1));
+    doc2.apply(visit(&doc2, 2));
+    doc1.merge(doc2);
+    assert_eq!(doc1.profile.visits.read(), 2u8.into());
+}
+
+#[test]
+fn empty_nested_op_is_rejected() {
+    let doc = Doc::default();
+    let op = DocCrdtOp {
+        dot: doc.v_clock.inc(1),
+        title_op: None,
+        profile_op: Some(ProfileCrdtOp {
+            names_op: None,
+            visits_op: None,
+        }),
+    };
+    assert_eq!(
+        doc.validate_op(&op),
+        Err(DocCmRDTError::Profile(ProfileCmRDTError::NoneOp))
+    );
+}
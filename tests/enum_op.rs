@@ -0,0 +1,99 @@
+use crdts::{CmRDT, Dot, GCounter, Map, Orswot};
+use crdts_macro::{crdt, ApplyOutcome, OrswotIntent};
+
+#[crdt(u64, embedded, op = "enum")]
+pub struct Profile {
+    tags: Orswot<String, u64>,
+    visits: GCounter<u64>,
+}
+
+#[crdt(u64, op = "enum", intents)]
+pub struct Data {
+    a: Orswot<String, u64>,
+    b: Map<u64, Orswot<Vec<u8>, u64>, u64>,
+    d: GCounter<u64>,
+}
+
+#[crdt(u64, op = "enum", delta)]
+pub struct Nested {
+    profile: Profile,
+    d: GCounter<u64>,
+}
+
+#[test]
+fn ops_only_carry_touched_fields() {
+    let mut data = Data::default();
+    let op = data.transact(1, |tx| {
+        tx.d(|d, actor| d.inc(actor))
+            .a(|a, ctx| a.add("x".into(), ctx));
+    });
+    assert_eq!(op.dot, Dot::new(1, 1));
+    assert!(matches!(op.ops[..], [DataFieldOp::D(_), DataFieldOp::A(_)]));
+
+    let json = serde_json::to_string(&op).unwrap();
+    assert!(!json.contains("b_op"), "{json}");
+    assert_eq!(serde_json::from_str::<DataCrdtOp>(&json).unwrap(), op);
+
+    assert_eq!(data.validate_op(&op), Ok(()));
+    let changes = data.apply_with_changes(op);
+    assert!(changes.a && !changes.b && changes.d);
+    assert!(data.a.contains(&"x".to_string()).val);
+    assert_eq!(data.d.read(), 1u8.into());
+
+    let op = data.intent_to_op(1, DataIntent::A(OrswotIntent::Rm("x".into())));
+    assert!(matches!(op.ops[..], [DataFieldOp::A(_)]));
+    assert_eq!(data.try_apply(op), ApplyOutcome::Applied);
+    assert!(!data.a.contains(&"x".to_string()).val);
+}
+
+#[test]
+fn setting_a_field_again_keeps_its_place() {
+    let data = Data::default();
+    let op = data.transact(1, |tx| {
+        tx.d(|d, actor| d.inc(actor))
+            .a(|a, ctx| a.add("x".into(), ctx))
+            .d(|d, actor| d.inc_many(actor, 2));
+    });
+    assert_eq!(op.ops.len(), 2);
+    assert_eq!(op.ops[0], DataFieldOp::D(Dot::new(1, 2)));
+}
+
+#[test]
+fn empty_ops_only_take_their_dot() {
+    let mut data = Data::default();
+    let op = data.transact(1, |_| {});
+    assert_eq!(data.validate_op(&op), Err(DataCmRDTError::NoneOp));
+    assert_eq!(data.try_apply(op.clone()), ApplyOutcome::Empty);
+    assert_eq!(data.v_clock.get(&1), 1);
+    assert_eq!(data.try_apply(op), ApplyOutcome::Duplicate);
+    assert_eq!(data.d.read(), 0u8.into());
+}
+
+#[test]
+fn embedded_enum_ops() {
+    let mut nested = Nested::default();
+    let op = nested.transact(1, |tx| {
+        tx.profile(|p, actor| {
+            let ctx = p.tags.read_ctx().derive_add_ctx(actor);
+            ProfileCrdtOp {
+                ops: vec![
+                    ProfileFieldOp::Visits(p.visits.inc(actor)),
+                    ProfileFieldOp::Tags(p.tags.add("x".into(), ctx)),
+                ],
+            }
+        });
+    });
+    assert_eq!(nested.validate_op(&op), Ok(()));
+    nested.apply(op);
+    assert_eq!(nested.profile.visits.read(), 1u8.into());
+    assert!(nested.profile.tags.contains(&"x".to_string()).val);
+    assert!(nested.field_clocks.get("profile").is_some());
+}
+
+#[test]
+fn unknown_field_ops_are_rejected() {
+    let json = r#"{"dot":{"actor":1,"counter":1},"ops":[{"Gone":{"actor":1,"counter":1}}]}"#;
+    assert!(serde_json::from_str::<DataCrdtOp>(json).is_err());
+    let json = r#"{"dot":{"actor":1,"counter":1},"ops":[],"gone_op":null}"#;
+    assert!(serde_json::from_str::<DataCrdtOp>(json).is_err());
+}
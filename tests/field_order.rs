@@ -0,0 +1,23 @@
+use crdts::{GCounter, Orswot};
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct Data {
+    z: GCounter<u64>,
+    a: Orswot<u8, u64>,
+    m: GCounter<u64>,
+}
+
+#[test]
+fn op_fields_follow_declaration_order() {
+    let data = Data::default();
+    let op = DataCrdtOp {
+        dot: data.v_clock.inc(1),
+        z_op: Some(data.z.inc(1)),
+        a_op: Some(data.a.rm(7, data.a.read_ctx().derive_rm_ctx())),
+        m_op: Some(data.m.inc(1)),
+    };
+    let json = serde_json::to_string(&op).unwrap();
+    let keys = ["\"z_op\"", "\"a_op\"", "\"m_op\"", "\"dot\""].map(|k| json.find(k).unwrap());
+    assert!(keys.windows(2).all(|w| w[0] < w[1]), "{json}");
+}
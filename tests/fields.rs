@@ -0,0 +1,73 @@
+use crdts::{Map, Orswot};
+use crdts_macro::{crdt, FieldInfo, UnknownField};
+
+#[crdt(u64)]
+pub struct Data {
+    a: Orswot<String, String>,
+    b: Map<u64, Orswot<Vec<u8>, u64>, u64>,
+    #[crdt(skip)]
+    cache: Vec<u8>,
+    some_counter: crdts::GCounter<u64>,
+    counted: <Hits as Counted>::Crdt,
+}
+
+pub trait Counted {
+    type Crdt;
+}
+
+pub struct Hits;
+
+impl Counted for Hits {
+    type Crdt = crdts::GCounter<u64>;
+}
+
+#[test]
+fn metadata() {
+    assert_eq!(
+        Data::FIELDS,
+        [
+            FieldInfo {
+                name: "a",
+                type_name: "Orswot<String, String>",
+                kind: "Orswot",
+            },
+            FieldInfo {
+                name: "b",
+                type_name: "Map<u64, Orswot<Vec<u8>, u64>, u64>",
+                kind: "Map",
+            },
+            FieldInfo {
+                name: "some_counter",
+                type_name: "crdts::GCounter<u64>",
+                kind: "GCounter",
+            },
+            FieldInfo {
+                name: "counted",
+                type_name: "<Hits as Counted>::Crdt",
+                kind: "Crdt",
+            },
+        ]
+    );
+    assert_eq!(
+        DataField::ALL,
+        [
+            DataField::A,
+            DataField::B,
+            DataField::SomeCounter,
+            DataField::Counted
+        ]
+    );
+    assert_eq!(DataField::SomeCounter.info().kind, "GCounter");
+}
+
+#[test]
+fn display_and_parse() {
+    for field in DataField::ALL {
+        assert_eq!(field.to_string().parse::<DataField>(), Ok(*field));
+    }
+    assert_eq!(DataField::SomeCounter.to_string(), "some_counter");
+    assert_eq!(
+        "cache".parse::<DataField>(),
+        Err(UnknownField("cache".to_string()))
+    );
+}
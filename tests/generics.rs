@@ -0,0 +1,78 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crdts::{Actor, CmRDT, CvRDT, GCounter, Orswot};
+use crdts_macro::crdt;
+
+#[crdt(A)]
+pub struct Doc<A: Actor + Debug, K: Hash + Eq + Clone + Debug> {
+    tags: Orswot<K, A>,
+    views: GCounter<A>,
+}
+
+#[crdt(A)]
+pub struct Tagged<A, K>
+where
+    A: Actor + Debug,
+    K: Hash + Eq + Clone + Debug,
+{
+    tags: Orswot<K, A>,
+}
+
+fn tag<A: Actor + Debug, K: Hash + Eq + Clone + Debug>(
+    doc: &Doc<A, K>,
+    actor: A,
+    tag: K,
+) -> DocCrdtOp<A, K> {
+    DocCrdtOp {
+        dot: doc.v_clock.inc(actor.clone()),
+        tags_op: Some(doc.tags.add(tag, doc.tags.read_ctx().derive_add_ctx(actor))),
+        views_op: None,
+    }
+}
+
+#[test]
+fn generic_ops_apply() {
+    let mut doc = Doc::<u64, String>::default();
+    let op = tag(&doc, 1, "x".to_string());
+    assert_eq!(doc.validate_op(&op), Ok(()));
+    doc.apply(op);
+    assert!(doc.tags.contains(&"x".to_string()).val);
+    assert_eq!(doc.v_clock.get(&1), 1);
+}
+
+#[test]
+fn generic_ops_round_trip() {
+    let doc = Doc::<u64, String>::default();
+    let op = tag(&doc, 1, "x".to_string());
+    let json = serde_json::to_string(&op).unwrap();
+    let back: DocCrdtOp<u64, String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, op);
+}
+
+#[test]
+fn generic_states_merge() {
+    let mut left = Doc::<u64, String>::default();
+    let mut right = Doc::<u64, String>::default();
+    left.apply(tag(&left, 1, "x".to_string()));
+    right.apply(tag(&right, 2, "y".to_string()));
+
+    assert_eq!(left.validate_merge(&right), Ok(()));
+    left.merge(right.clone());
+    right.merge(left.clone());
+    assert_eq!(left, right);
+    assert_eq!(left.tags.read().val.len(), 2);
+}
+
+#[test]
+fn where_clauses_are_kept() {
+    let mut tagged = Tagged::<String, u8>::default();
+    let actor = "a".to_string();
+    let ctx = tagged.tags.read_ctx().derive_add_ctx(actor.clone());
+    let op = TaggedCrdtOp {
+        dot: tagged.v_clock.inc(actor),
+        tags_op: Some(tagged.tags.add(7, ctx)),
+    };
+    tagged.apply(op);
+    assert!(tagged.tags.contains(&7).val);
+}
@@ -0,0 +1,69 @@
+use crdts::{CmRDT, GCounter, MVReg, Map, Orswot};
+use crdts_macro::{crdt, GCounterIntent, MVRegIntent, MapIntent, OrswotIntent};
+
+#[crdt(u64, embedded, intents)]
+pub struct Profile {
+    name: MVReg<String, u64>,
+    visits: GCounter<u64>,
+}
+
+#[crdt(u64, intents)]
+pub struct Data {
+    a: Orswot<String, u64>,
+    b: Map<String, Orswot<String, u64>, u64>,
+    d: GCounter<u64>,
+    profile: Profile,
+}
+
+#[test]
+fn intents_from_a_thin_client() {
+    let mut server = Data::default();
+    let intents = [
+        DataIntent::A(OrswotIntent::Add("x".to_string())),
+        DataIntent::D(GCounterIntent::Inc),
+        DataIntent::D(GCounterIntent::IncBy(2)),
+        DataIntent::B(MapIntent::Update {
+            key: "k".to_string(),
+            intent: OrswotIntent::Add("y".to_string()),
+        }),
+        DataIntent::Profile(ProfileIntent::Name(MVRegIntent::Write("ann".to_string()))),
+    ];
+    for intent in intents {
+        // clients only send the intent
+        let json = serde_json::to_string(&intent).unwrap();
+        let intent: DataIntent = serde_json::from_str(&json).unwrap();
+
+        let op = server.intent_to_op(1, intent);
+        assert_eq!(server.validate_op(&op), Ok(()));
+        server.apply(op);
+    }
+    assert_eq!(server.v_clock.get(&1), 5);
+    assert!(server.a.contains(&"x".to_string()).val);
+    assert_eq!(server.d.read(), 3u64.into());
+    let k = server.b.get(&"k".to_string()).val.unwrap();
+    assert!(k.contains(&"y".to_string()).val);
+    assert_eq!(server.profile.name.read().val, ["ann"]);
+
+    let op = server.intent_to_op(2, DataIntent::A(OrswotIntent::Rm("x".to_string())));
+    server.apply(op);
+    assert!(!server.a.contains(&"x".to_string()).val);
+    let op = server.intent_to_op(2, DataIntent::B(MapIntent::Rm("k".to_string())));
+    server.apply(op);
+    assert!(server.b.get(&"k".to_string()).val.is_none());
+}
+
+#[test]
+fn replicas_agree_on_intent_ops() {
+    let mut left = Data::default();
+    let mut right = Data::default();
+    let ops = [
+        left.intent_to_op(1, DataIntent::A(OrswotIntent::Add("x".to_string()))),
+        right.intent_to_op(2, DataIntent::A(OrswotIntent::Add("y".to_string()))),
+    ];
+    for op in ops {
+        left.apply(op.clone());
+        right.apply(op);
+    }
+    assert_eq!(left, right);
+    assert_eq!(left.a.read().val.len(), 2);
+}
@@ -0,0 +1,123 @@
+use crdts::{CmRDT, CvRDT, GCounter, Orswot, VClock};
+use crdts_macro::{crdt, Causal, CausalBuffer, CRDT};
+
+#[crdt(u64)]
+pub struct Data {
+    a: Orswot<String, u64>,
+    b: GCounter<u64>,
+}
+
+#[derive(Default)]
+struct Index {
+    names: Vec<String>,
+    b_calls: usize,
+}
+
+impl DataObserver for Index {
+    fn on_a_changed(&mut self, a: &Orswot<String, u64>) {
+        self.names = a.read().val.into_iter().collect();
+        self.names.sort();
+    }
+
+    fn on_b_changed(&mut self, _: &GCounter<u64>) {
+        self.b_calls += 1;
+    }
+}
+
+#[test]
+fn hooks_follow_changes() {
+    let mut index = Index::default();
+    let mut data = Data::default();
+    let op = data.transact(1, |tx| {
+        tx.a(|a, ctx| a.add("x".to_string(), ctx));
+    });
+    data.apply_observed(op.clone(), &mut index);
+    data.apply_observed(op, &mut index);
+    assert_eq!(index.names, ["x"]);
+    assert_eq!(index.b_calls, 0);
+
+    let mut remote = Data::default();
+    let op = remote.transact(2, |tx| {
+        tx.a(|a, ctx| a.add("y".to_string(), ctx));
+        tx.b(|b, actor| b.inc(actor));
+    });
+    remote.apply(op);
+    data.merge_observed(remote.clone(), &mut index);
+    assert_eq!(index.names, ["x", "y"]);
+    assert_eq!(index.b_calls, 1);
+
+    // observers only care about some fields
+    struct Nothing;
+    impl DataObserver for Nothing {}
+    data.merge_observed(remote, &mut Nothing);
+}
+
+#[test]
+fn buffered_ops() {
+    let mut remote = Data::default();
+    let ops = (0..2)
+        .map(|_| {
+            let op = remote.transact(1, |tx| {
+                tx.b(|b, actor| b.inc(actor));
+            });
+            remote.apply(op.clone());
+            op
+        })
+        .collect::<Vec<_>>();
+
+    let mut index = Index::default();
+    let mut data = Data::default();
+    let mut buffer = CausalBuffer::<Data>::new();
+    buffer.push(ops[1].clone());
+    buffer.push(ops[0].clone());
+    while let Some(op) = buffer.pop_ready(data.clock()) {
+        data.apply_observed(op, &mut index);
+    }
+    assert_eq!(index.b_calls, 2);
+}
+
+/// A field that is neither `Clone` nor `PartialEq`.
+#[derive(Default)]
+pub struct Hits(GCounter<u64>);
+
+impl CmRDT for Hits {
+    type Op = <GCounter<u64> as CmRDT>::Op;
+    type Validation = <GCounter<u64> as CmRDT>::Validation;
+
+    fn apply(&mut self, op: Self::Op) {
+        self.0.apply(op)
+    }
+
+    fn validate_op(&self, op: &Self::Op) -> Result<(), Self::Validation> {
+        self.0.validate_op(op)
+    }
+}
+
+impl CvRDT for Hits {
+    type Validation = <GCounter<u64> as CvRDT>::Validation;
+
+    fn validate_merge(&self, other: &Self) -> Result<(), Self::Validation> {
+        self.0.validate_merge(&other.0)
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.0.merge(other.0)
+    }
+}
+
+#[derive(Default, CRDT)]
+#[crdt(u64)]
+pub struct Uncloned {
+    hits: Hits,
+    v_clock: VClock<u64>,
+}
+
+#[test]
+fn fields_without_clone() {
+    let mut data = Uncloned::default();
+    let op = data.transact(1, |tx| {
+        tx.set_hits_op(data.hits.0.inc(1));
+    });
+    data.apply(op);
+    assert_eq!(data.hits.0.read(), 1u8.into());
+}
@@ -0,0 +1,75 @@
+use crdts::{CmRDT, GCounter, Orswot};
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct Data {
+    #[crdt(tag = 1)]
+    a: Orswot<String, u64>,
+    #[crdt(tag = 2)]
+    d: GCounter<u64>,
+    plain: GCounter<u64>,
+}
+
+#[crdt(u64)]
+pub struct Renamed {
+    #[crdt(tag = 1)]
+    names: Orswot<String, u64>,
+    #[crdt(tag = 2)]
+    visits: GCounter<u64>,
+    plain: GCounter<u64>,
+}
+
+#[crdt(u64, op = "enum")]
+pub struct Compact {
+    #[crdt(tag = 1)]
+    a: Orswot<String, u64>,
+    #[crdt(tag = 2)]
+    d: GCounter<u64>,
+}
+
+#[test]
+fn none_ops_are_not_serialized() {
+    let data = Data::default();
+    let op = data.transact(1, |tx| {
+        tx.plain(|p, actor| p.inc(actor));
+    });
+    let json = serde_json::to_string(&op).unwrap();
+    assert_eq!(
+        json,
+        r#"{"plain_op":{"actor":1,"counter":1},"dot":{"actor":1,"counter":1}}"#
+    );
+    assert_eq!(serde_json::from_str::<DataCrdtOp>(&json).unwrap(), op);
+}
+
+#[test]
+fn tags_survive_field_renames() {
+    let mut data = Data::default();
+    let op = data.transact(1, |tx| {
+        tx.a(|a, ctx| a.add("x".into(), ctx))
+            .d(|d, actor| d.inc(actor));
+    });
+    let json = serde_json::to_string(&op).unwrap();
+    assert!(
+        json.contains(r#""1":{"Add""#) && json.contains(r#""2":{"actor""#),
+        "{json}"
+    );
+    data.apply(op);
+
+    let op: RenamedCrdtOp = serde_json::from_str(&json).unwrap();
+    let mut renamed = Renamed::default();
+    renamed.apply(op);
+    assert_eq!(renamed.names, data.a);
+    assert_eq!(renamed.visits, data.d);
+}
+
+#[test]
+fn tags_name_enum_ops() {
+    let compact = Compact::default();
+    let op = compact.transact(1, |tx| {
+        tx.d(|d, actor| d.inc(actor));
+    });
+    assert_eq!(
+        serde_json::to_string(&op).unwrap(),
+        r#"{"dot":{"actor":1,"counter":1},"ops":[{"2":{"actor":1,"counter":1}}]}"#
+    );
+}
@@ -0,0 +1,139 @@
+use crdts::{CmRDT, GCounter, Orswot, VClock};
+use crdts_macro::crdt;
+use crdts_macro::oplog::{FileStore, MemoryStore, OpLog, OpStore};
+
+#[crdt(u64)]
+pub struct Data {
+    a: Orswot<String, u64>,
+    b: GCounter<u64>,
+}
+
+fn record<S: OpStore<DataCrdtOp>>(data: &mut Data, log: &mut OpLog<Data, S>, actor: u64)
+where
+    S::Error: std::fmt::Debug,
+{
+    let op = data.transact(actor, |tx| {
+        tx.a(|a, ctx| a.add(format!("{actor}"), ctx));
+        tx.b(|b, actor| b.inc(actor));
+    });
+    data.apply(op.clone());
+    log.append(op).unwrap();
+}
+
+#[test]
+fn replay_from_memory() {
+    let mut data = Data::default();
+    let mut log = OpLog::open(MemoryStore::default()).unwrap();
+    record(&mut data, &mut log, 1);
+    record(&mut data, &mut log, 2);
+    record(&mut data, &mut log, 1);
+    assert_eq!(log.len(), 3);
+    assert_eq!(log.replay(), data);
+
+    let mut clock = VClock::new();
+    clock.apply(crdts::Dot::new(1, 1));
+    let counters = log
+        .since(&clock)
+        .map(|op| (op.dot.actor, op.dot.counter))
+        .collect::<Vec<_>>();
+    assert_eq!(counters, vec![(2, 1), (1, 2)]);
+}
+
+#[test]
+fn replay_from_file() {
+    let path = std::env::temp_dir().join(format!("crdts_macro_oplog_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut data = Data::default();
+    let mut log = OpLog::open(FileStore::new(&path)).unwrap();
+    assert!(log.is_empty());
+    record(&mut data, &mut log, 1);
+    record(&mut data, &mut log, 2);
+    drop(log);
+
+    let log = OpLog::<Data, _>::open(FileStore::new(&path)).unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log.replay(), data);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn truncated_record() {
+    let path = std::env::temp_dir().join(format!(
+        "crdts_macro_oplog_truncated_{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+
+    let mut data = Data::default();
+    let mut log = OpLog::open(FileStore::new(&path)).unwrap();
+    record(&mut data, &mut log, 1);
+    drop(log);
+
+    // a crash in the middle of writing the second op
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    std::io::Write::write_all(&mut file, br#"{"dot":{"actor":2,"#).unwrap();
+    drop(file);
+
+    // loading alone leaves the file as it is
+    let store = FileStore::new(&path);
+    let before = std::fs::read(&path).unwrap();
+    assert_eq!(OpStore::<DataCrdtOp>::load(&store).unwrap().len(), 1);
+    assert_eq!(std::fs::read(&path).unwrap(), before);
+
+    let mut log = OpLog::<Data, _>::open(store).unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log.replay(), data);
+    record(&mut data, &mut log, 2);
+    drop(log);
+
+    let log = OpLog::<Data, _>::open(FileStore::new(&path)).unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log.replay(), data);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn missing_last_newline() {
+    let path = std::env::temp_dir().join(format!(
+        "crdts_macro_oplog_newline_{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+
+    let mut data = Data::default();
+    let mut log = OpLog::open(FileStore::new(&path)).unwrap();
+    record(&mut data, &mut log, 1);
+    record(&mut data, &mut log, 2);
+    drop(log);
+
+    // the last op is whole, only its newline is missing
+    let mut bytes = std::fs::read(&path).unwrap();
+    assert_eq!(bytes.pop(), Some(b'\n'));
+    std::fs::write(&path, &bytes).unwrap();
+    let store = FileStore::new(&path);
+    assert_eq!(OpStore::<DataCrdtOp>::load(&store).unwrap().len(), 2);
+
+    let mut log = OpLog::<Data, _>::open(store).unwrap();
+    assert_eq!(log.len(), 2);
+    record(&mut data, &mut log, 1);
+    drop(log);
+
+    let log = OpLog::<Data, _>::open(FileStore::new(&path)).unwrap();
+    assert_eq!(log.len(), 3);
+    assert_eq!(log.replay(), data);
+
+    // a whole log opens without being written to
+    let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(&path, permissions.clone()).unwrap();
+    let log = OpLog::<Data, _>::open(FileStore::new(&path)).unwrap();
+    assert_eq!(log.len(), 3);
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    std::fs::set_permissions(&path, permissions).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,49 @@
+use crdts::{CmRDT, CvRDT, GCounter, Orswot, VClock};
+use crdts_macro::{crdt, CRDT};
+
+#[crdt(u64, view, read_ctx, intents, delta)]
+pub struct Data {
+    r#type: GCounter<u64>,
+    r#match: Orswot<String, u64>,
+}
+
+#[crdt(u64, op = "enum")]
+pub struct Compact {
+    r#type: GCounter<u64>,
+}
+
+#[derive(Default, Clone, CRDT)]
+#[crdt(u64)]
+pub struct Derived {
+    r#type: GCounter<u64>,
+    v_clock: VClock<u64>,
+}
+
+#[test]
+fn raw_fields() {
+    let mut data = Data::default();
+    let op = data.transact(1, |tx| {
+        tx.r#type(|t, actor| t.inc(actor))
+            .r#match(|m, ctx| m.add("x".into(), ctx));
+    });
+    let json = serde_json::to_string(&op).unwrap();
+    assert!(json.contains(r#""type_op""#), "{json}");
+    data.apply(op);
+    assert_eq!(data.view().r#type, 1);
+    assert_eq!(Data::FIELDS[0].name, "type");
+    assert_eq!("match".parse::<DataField>(), Ok(DataField::Match));
+
+    let compact = Compact::default();
+    let op = compact.transact(1, |tx| {
+        tx.r#type(|t, actor| t.inc(actor));
+    });
+    assert!(matches!(op.ops[..], [CompactFieldOp::Type(_)]));
+
+    let mut derived = Derived::default();
+    let op = derived.transact(1, |tx| {
+        tx.r#type(|t, actor| t.inc(actor));
+    });
+    derived.apply(op);
+    derived.merge(Derived::default());
+    assert_eq!(derived.r#type.read(), 1u8.into());
+}
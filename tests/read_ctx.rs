@@ -0,0 +1,58 @@
+use crdts::{CmRDT, GCounter, MVReg, Orswot};
+use crdts_macro::{crdt, DeriveCtx};
+
+#[crdt(u64, embedded, read_ctx)]
+pub struct Profile {
+    name: MVReg<String, u64>,
+    visits: GCounter<u64>,
+}
+
+#[crdt(u64, read_ctx)]
+pub struct Data {
+    a: Orswot<String, u64>,
+    d: GCounter<u64>,
+    profile: Profile,
+}
+
+/// A client holding only the context, not the state.
+fn client_op(ctx: &DataReadCtx, actor: u64) -> DataCrdtOp {
+    let names = Orswot::<String, u64>::new();
+    DataCrdtOp {
+        dot: ctx.v_clock.inc(actor),
+        a_op: Some(names.add("x".to_string(), ctx.a.add_ctx(actor))),
+        d_op: None,
+        profile_op: Some(ProfileCrdtOp {
+            name_op: Some(MVReg::new().write("ann".to_string(), ctx.profile.name.add_ctx(actor))),
+            visits_op: None,
+        }),
+    }
+}
+
+#[test]
+fn ops_from_a_serialized_ctx() {
+    let mut data = Data::default();
+    let op = data.transact(1, |tx| {
+        tx.a(|a, ctx| a.add("old".to_string(), ctx));
+        tx.d(|d, actor| d.inc(actor));
+    });
+    data.apply(op);
+
+    let json = serde_json::to_string(&data.read_ctx()).unwrap();
+    let ctx: DataReadCtx = serde_json::from_str(&json).unwrap();
+    assert_eq!(ctx, data.read_ctx());
+    assert_eq!(ctx.d, ());
+
+    let op = client_op(&ctx, 2);
+    assert_eq!(data.validate_op(&op), Ok(()));
+    data.apply(op);
+    assert!(data.a.contains(&"x".to_string()).val);
+    assert_eq!(data.profile.name.read().val, ["ann"]);
+
+    // removing with the client's context only removes what it has seen
+    let ctx = data.read_ctx();
+    let op = data.transact(2, |tx| {
+        tx.a(|a, _| a.rm("old".to_string(), ctx.a.rm_ctx()));
+    });
+    data.apply(op);
+    assert!(!data.a.contains(&"old".to_string()).val);
+}
@@ -0,0 +1,77 @@
+use crdts::{CmRDT, CvRDT, GCounter, GSet, Map, Orswot};
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct Profile {
+    names: Orswot<String, u64>,
+    visits: GCounter<u64>,
+}
+
+// `GSet` has no `ResetRemove` and `Orswot<_, String>` only one for another
+// actor, this must still compile
+#[crdt(u64)]
+pub struct Tags {
+    tags: GSet<String>,
+    names: Orswot<String, String>,
+}
+
+fn add_name(profile: &Profile, actor: u64, name: &str) -> ProfileCrdtOp {
+    ProfileCrdtOp {
+        dot: profile.v_clock.inc(actor),
+        names_op: Some(profile.names.add(
+            name.to_string(),
+            profile.names.read_ctx().derive_add_ctx(actor),
+        )),
+        visits_op: Some(profile.visits.inc(actor)),
+    }
+}
+
+fn update(profiles: &mut Map<u64, Profile, u64>, actor: u64, name: &str) {
+    let add_ctx = profiles.read_ctx().derive_add_ctx(actor);
+    let op = profiles.update(7u64, add_ctx, |profile, _| add_name(profile, actor, name));
+    profiles.apply(op);
+}
+
+#[test]
+fn profiles_in_a_map() {
+    let mut profiles = Map::new();
+    update(&mut profiles, 1, "alice");
+    update(&mut profiles, 1, "bob");
+
+    let profile = profiles.get(&7).val.unwrap();
+    assert_eq!(profile.names.read().val.len(), 2);
+    assert_eq!(profile.visits.read(), 2u8.into());
+}
+
+#[test]
+fn concurrent_remove_and_update() {
+    let mut replica1 = Map::new();
+    update(&mut replica1, 1, "alice");
+    let mut replica2 = replica1.clone();
+
+    let rm_ctx = replica1.get(&7).derive_rm_ctx();
+    replica1.apply(replica1.rm(7u64, rm_ctx));
+    update(&mut replica2, 2, "bob");
+
+    let mut merged1 = replica1.clone();
+    merged1.merge(replica2.clone());
+    let mut merged2 = replica2;
+    merged2.merge(replica1);
+    assert_eq!(merged1, merged2);
+
+    // the update survives the remove, what the remove observed does not
+    let profile = merged1.get(&7).val.unwrap();
+    assert_eq!(
+        profile.names.read().val,
+        ["bob".to_string()].into_iter().collect()
+    );
+    assert_eq!(profile.visits.read(), 1u8.into());
+}
+
+#[test]
+fn fields_without_reset_remove() {
+    let mut tags = Tags::default();
+    tags.merge(Tags::default());
+    assert!(tags.tags.read().is_empty());
+    assert!(tags.names.read().val.is_empty());
+}
@@ -0,0 +1,118 @@
+use crdts::{CmRDT, GCounter, Orswot};
+use crdts_macro::{ApplyOutcome, CausalBuffer};
+
+mod v1 {
+    use super::*;
+    use crdts_macro::crdt;
+
+    #[crdt(u64)]
+    pub struct Data {
+        pub a: Orswot<String, u64>,
+    }
+}
+
+mod v2 {
+    use super::*;
+    use crdts_macro::crdt;
+
+    #[crdt(u64)]
+    pub struct Data {
+        pub a: Orswot<String, u64>,
+        pub b: GCounter<u64>,
+    }
+
+    #[crdt(u64, unknown_ops = "reject")]
+    pub struct Strict {
+        pub a: Orswot<String, u64>,
+    }
+}
+
+fn convert<T: serde::Serialize, U: serde::de::DeserializeOwned>(
+    value: &T,
+) -> serde_json::Result<U> {
+    serde_json::from_str(&serde_json::to_string(value)?)
+}
+
+#[test]
+fn states_across_versions() {
+    let mut old = v1::Data::default();
+    let op = old.transact(1, |tx| {
+        tx.a(|a, ctx| a.add("x".to_string(), ctx));
+    });
+    old.apply(op);
+
+    let new: v2::Data = convert(&old).unwrap();
+    assert!(new.a.contains(&"x".to_string()).val);
+    assert_eq!(new.b, GCounter::new());
+
+    let back: v1::Data = convert(&new).unwrap();
+    assert_eq!(back, old);
+}
+
+#[test]
+fn ops_across_versions() {
+    let mut old = v1::Data::default();
+    let mut new = v2::Data::default();
+
+    let op = old.transact(1, |tx| {
+        tx.a(|a, ctx| a.add("x".to_string(), ctx));
+    });
+    old.apply(op.clone());
+    new.apply(convert(&op).unwrap());
+
+    // the op for `b` is unknown to v1 and dropped, the one for `a` still applies
+    let op = new.transact(2, |tx| {
+        tx.a(|a, ctx| a.add("y".to_string(), ctx));
+        tx.b(|b, actor| b.inc(actor));
+    });
+    new.apply(op.clone());
+    let old_op: v1::DataCrdtOp = convert(&op).unwrap();
+    assert!(old_op.a_op.is_some());
+    old.apply(old_op);
+    assert_eq!(old.a, new.a);
+
+    assert!(convert::<_, v2::StrictCrdtOp>(&op).is_err());
+}
+
+#[test]
+fn unknown_only_ops_keep_their_dot() {
+    let mut new = v2::Data::default();
+    let only_b = new.transact(1, |tx| {
+        tx.b(|b, actor| b.inc(actor));
+    });
+    new.apply(only_b.clone());
+    let then_a = new.transact(1, |tx| {
+        tx.a(|a, ctx| a.add("x".to_string(), ctx));
+    });
+    new.apply(then_a.clone());
+    let only_b: v1::DataCrdtOp = convert(&only_b).unwrap();
+    let then_a: v1::DataCrdtOp = convert(&then_a).unwrap();
+
+    let mut old = v1::Data::default();
+    assert_eq!(old.try_apply(only_b.clone()), ApplyOutcome::Empty);
+    assert_eq!(old.try_apply(then_a.clone()), ApplyOutcome::Applied);
+    assert_eq!(old.a, new.a);
+
+    let mut old = v1::Data::default();
+    let mut buffer = CausalBuffer::<v1::Data>::new();
+    buffer.push(then_a);
+    buffer.push(only_b);
+    assert_eq!(buffer.deliver(&mut old), 2);
+    assert!(buffer.is_empty());
+    assert_eq!(old.a, new.a);
+}
+
+#[test]
+fn reject_unknown_ops() {
+    let old = v1::Data::default();
+    let op = old.transact(1, |tx| {
+        tx.a(|a, ctx| a.add("x".to_string(), ctx));
+    });
+    let mut strict = v2::Strict::default();
+    strict.apply(convert(&op).unwrap());
+    assert!(strict.a.contains(&"x".to_string()).val);
+
+    let mut json: serde_json::Value = serde_json::to_value(&op).unwrap();
+    json["b_op"] = serde_json::Value::Null;
+    assert!(serde_json::from_value::<v2::StrictCrdtOp>(json).is_err());
+}
@@ -0,0 +1,42 @@
+use crdts::{CmRDT, CvRDT, GCounter};
+use crdts_macro::crdt;
+
+fn keep_max(local: &mut u64, other: u64) {
+    *local = (*local).max(other);
+}
+
+#[crdt(u64)]
+pub struct Data {
+    a: GCounter<u64>,
+    #[crdt(skip)]
+    cache: Vec<u8>,
+    #[crdt(skip, merge = "keep_max")]
+    seen: u64,
+}
+
+#[test]
+fn skipped_fields_have_no_op_slot() {
+    let mut data = Data::default();
+    data.apply(DataCrdtOp {
+        dot: data.v_clock.inc(1),
+        a_op: Some(data.a.inc(1)),
+    });
+    assert_eq!(data.a.read(), 1u8.into());
+}
+
+#[test]
+fn skipped_fields_use_merge_fn() {
+    let mut data1 = Data {
+        cache: vec![1],
+        seen: 3,
+        ..Default::default()
+    };
+    let data2 = Data {
+        cache: vec![2],
+        seen: 7,
+        ..Default::default()
+    };
+    data1.merge(data2);
+    assert_eq!(data1.cache, vec![1]);
+    assert_eq!(data1.seen, 7);
+}
@@ -0,0 +1,61 @@
+use std::io;
+
+use crdts::{CmRDT, GCounter, Orswot};
+use crdts_macro::oplog::{FileStore, OpLog};
+use crdts_macro::{crdt, snapshot, Causal};
+
+#[crdt(u64)]
+pub struct Data {
+    a: Orswot<String, u64>,
+    b: GCounter<u64>,
+}
+
+fn record(data: &mut Data, log: &mut OpLog<Data, FileStore>, actor: u64) {
+    let op = data.transact(actor, |tx| {
+        tx.a(|a, ctx| a.add(format!("{actor}"), ctx));
+        tx.b(|b, actor| b.inc(actor));
+    });
+    data.apply(op.clone());
+    log.append(op).unwrap();
+}
+
+#[test]
+fn snapshot_compact_and_restore() {
+    let dir = std::env::temp_dir().join(format!("crdts_macro_snapshot_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (ops, snap) = (dir.join("data.ops"), dir.join("data.snap"));
+
+    let mut data = Data::default();
+    let mut log = OpLog::open(FileStore::new(&ops)).unwrap();
+    record(&mut data, &mut log, 1);
+    record(&mut data, &mut log, 2);
+    snapshot::save(&snap, &data).unwrap();
+    assert_eq!(log.compact(data.clock()).unwrap(), 2);
+    record(&mut data, &mut log, 1);
+    drop(log);
+
+    let log = OpLog::<Data, _>::open(FileStore::new(&ops)).unwrap();
+    assert_eq!(log.len(), 1);
+    let restored = log.restore(snapshot::load(&snap).unwrap().unwrap());
+    assert_eq!(restored, data);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn corrupt_snapshot() {
+    let mut data = Data::default();
+    let op = data.transact(1, |tx| {
+        tx.b(|b, actor| b.inc(actor));
+    });
+    data.apply(op);
+    let mut bytes = snapshot::encode(&data).unwrap();
+    assert_eq!(snapshot::decode::<Data>(&bytes).unwrap(), data);
+
+    let last = bytes.len() - 2;
+    bytes[last] ^= 1;
+    let err = snapshot::decode::<Data>(&bytes).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let err = snapshot::decode::<Data>(&bytes[..10]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,110 @@
+use crdts::{GCounter, Orswot};
+use crdts_macro::crdt;
+use crdts_macro::sync::{MemoryTransport, Replica, SyncMessage};
+
+#[crdt(u64, delta)]
+pub struct Data {
+    a: Orswot<String, u64>,
+    b: GCounter<u64>,
+}
+
+type Node = Replica<Data, MemoryTransport<SyncMessage<Data>>>;
+
+fn nodes() -> (Node, Node) {
+    let (t1, t2) = MemoryTransport::pair();
+    (
+        Replica::new(Data::default(), t1),
+        Replica::new(Data::default(), t2),
+    )
+}
+
+fn run(r1: &mut Node, r2: &mut Node) {
+    while r1.poll().unwrap() + r2.poll().unwrap() > 0 {}
+}
+
+fn add(node: &mut Node, actor: u64, name: &str) {
+    let op = node.state().transact(actor, |tx| {
+        tx.a(|a, ctx| a.add(name.to_string(), ctx));
+    });
+    node.apply(op).unwrap();
+}
+
+fn inc(node: &mut Node, actor: u64) {
+    let op = node.state().transact(actor, |tx| {
+        tx.b(|b, actor| b.inc(actor));
+    });
+    node.apply(op).unwrap();
+}
+
+#[test]
+fn live_ops() {
+    let (mut r1, mut r2) = nodes();
+    add(&mut r1, 1, "x");
+    inc(&mut r2, 2);
+    run(&mut r1, &mut r2);
+    assert_eq!(r1.state(), r2.state());
+    assert_eq!(r1.state().b.read(), 1u8.into());
+}
+
+#[test]
+fn partition_and_heal() {
+    let (mut r1, mut r2) = nodes();
+    add(&mut r1, 1, "x");
+    run(&mut r1, &mut r2);
+
+    r1.transport().partition();
+    add(&mut r1, 1, "y");
+    inc(&mut r1, 1);
+    inc(&mut r2, 2);
+    run(&mut r1, &mut r2);
+    assert_ne!(r1.state(), r2.state());
+    assert!(!r1.is_synced() && !r2.is_synced());
+
+    r1.transport().heal();
+    r1.hello().unwrap();
+    run(&mut r1, &mut r2);
+    assert_eq!(r1.state(), r2.state());
+    assert!(r1.is_synced() && r2.is_synced());
+    assert_eq!(r2.state().b.read(), 2u8.into());
+    assert!(r2.state().a.contains(&"y".to_string()).val);
+}
+
+#[test]
+fn gap_after_heal() {
+    let (mut r1, mut r2) = nodes();
+    r1.transport().partition();
+    add(&mut r1, 1, "x");
+    r1.transport().heal();
+
+    // the second op can't be delivered before the lost one, r2 asks for it
+    inc(&mut r1, 1);
+    run(&mut r1, &mut r2);
+    assert_eq!(r1.state(), r2.state());
+    assert!(r1.is_synced());
+}
+
+#[crdt(u64)]
+pub struct Plain {
+    b: GCounter<u64>,
+}
+
+#[test]
+fn without_delta() {
+    let (t1, t2) = MemoryTransport::pair();
+    let mut r1: Replica<Plain, _> = Replica::new(Plain::default(), t1);
+    let mut r2: Replica<Plain, MemoryTransport<SyncMessage<Plain>>> =
+        Replica::new(Plain::default(), t2);
+    r1.transport().partition();
+    let op = r1.state().transact(1, |tx| {
+        tx.b(|b, actor| b.inc(actor));
+    });
+    r1.apply(op).unwrap();
+    r1.transport().heal();
+
+    // the whole state is sent in place of a delta
+    r1.hello().unwrap();
+    while r1.poll().unwrap() + r2.poll().unwrap() > 0 {}
+    assert_eq!(r1.state(), r2.state());
+    assert!(r1.is_synced() && r2.is_synced());
+    assert_eq!(r2.state().b.read(), 1u8.into());
+}
@@ -0,0 +1,75 @@
+use crdts::{CmRDT, CvRDT, GCounter, Map, Orswot};
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct Data {
+    a: Orswot<String, String>,
+    b: Map<u64, Orswot<Vec<u8>, u64>, u64>,
+    c: Orswot<Vec<u8>, u64>,
+    d: GCounter<u64>,
+}
+
+#[test]
+fn transact_collects_touched_fields() {
+    let mut data = Data::default();
+    let actor = 1;
+    let op = data.transact(actor, |tx| {
+        // `a` has `String` actors, its context is not the transaction's
+        let ctx = data.a.read_ctx().derive_add_ctx(actor.to_string());
+        tx.set_a_op(data.a.add("x".into(), ctx))
+            .d(|d, actor| d.inc(actor));
+    });
+    assert_eq!(op.dot, crdts::Dot::new(actor, 1));
+    assert!(op.a_op.is_some() && op.d_op.is_some());
+    assert!(op.b_op.is_none() && op.c_op.is_none());
+
+    assert_eq!(data.validate_op(&op), Ok(()));
+    data.apply(op);
+    assert!(data.a.contains(&"x".to_string()).val);
+    assert_eq!(data.d.read(), 1u8.into());
+
+    let op = data.transact(actor, |tx| {
+        tx.b(|b, ctx| b.update(7u64, ctx, |v, ctx| v.add(vec![1], ctx)));
+    });
+    assert_eq!(op.dot, crdts::Dot::new(actor, 2));
+    data.apply(op);
+    assert!(data.b.get(&7).val.is_some());
+}
+
+#[test]
+fn transact_ops_replicate() {
+    let mut data1 = Data::default();
+    let mut data2 = Data::default();
+    let op = data1.transact(1, |tx| {
+        tx.c(|c, ctx| c.add(vec![1], ctx));
+    });
+    data1.apply(op.clone());
+    data2.apply(op);
+    assert_eq!(data1, data2);
+
+    let op = data2.transact(2, |tx| {
+        tx.d(|d, actor| d.inc(actor));
+    });
+    data2.apply(op);
+    data1.merge(data2.clone());
+    assert_eq!(data1, data2);
+}
+
+#[crdt(u64)]
+pub struct Slots {
+    a: GCounter<u64>,
+    a_op: GCounter<u64>,
+}
+
+#[test]
+fn fields_named_like_op_slots() {
+    let mut slots = Slots::default();
+    let op = slots.transact(1, |tx| {
+        tx.a(|a, actor| a.inc(actor))
+            .a_op(|a_op, actor| a_op.inc_many(actor, 2));
+    });
+    assert!(op.a_op.is_some() && op.a_op_op.is_some());
+    slots.apply(op);
+    assert_eq!(slots.a.read(), 1u8.into());
+    assert_eq!(slots.a_op.read(), 2u8.into());
+}
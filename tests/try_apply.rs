@@ -0,0 +1,33 @@
+use crdts::{Dot, GCounter, Orswot};
+use crdts_macro::{crdt, ApplyOutcome};
+
+#[crdt(u64)]
+pub struct Data {
+    a: Orswot<String, u64>,
+    b: GCounter<u64>,
+}
+
+#[test]
+fn outcomes() {
+    let mut data = Data::default();
+    let op = data.transact(1, |tx| {
+        tx.b(|b, actor| b.inc(actor));
+    });
+    assert_eq!(data.try_apply(op.clone()), ApplyOutcome::Applied);
+    assert_eq!(data.try_apply(op), ApplyOutcome::Duplicate);
+
+    let op = data.transact(1, |_| {});
+    assert_eq!(data.try_apply(op), ApplyOutcome::Empty);
+    assert_eq!(data.v_clock.get(&1), 2);
+
+    let op = DataCrdtOp {
+        dot: Dot::new(1, 5),
+        a_op: None,
+        b_op: Some(data.b.inc(1)),
+    };
+    assert!(matches!(
+        data.try_apply(op),
+        ApplyOutcome::Invalid(DataCmRDTError::VClock(_))
+    ));
+    assert_eq!(data.b.read(), 1u8.into());
+}
@@ -0,0 +1,9 @@
+use crdts::GCounter;
+use crdts_macro::crdt;
+
+#[crdt(u64, embedded, delta)]
+pub struct Data {
+    a: GCounter<u64>,
+}
+
+fn main() {}
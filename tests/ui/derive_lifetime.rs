@@ -0,0 +1,11 @@
+use crdts::{GCounter, VClock};
+use crdts_macro::CRDT;
+
+#[derive(CRDT)]
+pub struct Data<'a> {
+    a: GCounter<u64>,
+    name: &'a str,
+    v_clock: VClock<u64>,
+}
+
+fn main() {}
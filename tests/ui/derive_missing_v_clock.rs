@@ -0,0 +1,9 @@
+use crdts::GCounter;
+use crdts_macro::CRDT;
+
+#[derive(CRDT)]
+pub struct Data {
+    a: GCounter<u64>,
+}
+
+fn main() {}
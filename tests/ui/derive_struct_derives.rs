@@ -0,0 +1,11 @@
+use crdts::{GCounter, VClock};
+use crdts_macro::CRDT;
+
+#[derive(CRDT)]
+#[crdt(no_default)]
+pub struct Data {
+    a: GCounter<u64>,
+    v_clock: VClock<u64>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use crdts::GCounter;
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct Data {
+    dot: GCounter<u64>,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use crdts::GCounter;
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct Data {
+    #[crdt(tag = 1)]
+    a: GCounter<u64>,
+    #[crdt(tag = 1)]
+    b: GCounter<u64>,
+}
+
+fn main() {}
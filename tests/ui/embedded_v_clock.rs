@@ -0,0 +1,10 @@
+use crdts::{GCounter, VClock};
+use crdts_macro::crdt;
+
+#[crdt(u64, embedded)]
+pub struct Data {
+    a: GCounter<u64>,
+    v_clock: VClock<u64>,
+}
+
+fn main() {}
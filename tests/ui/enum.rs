@@ -0,0 +1,8 @@
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub enum Data {
+    A,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use crdts::GCounter;
+use crdts_macro::crdt;
+
+#[crdt(u64, op = "enum", unknown_ops = "ignore")]
+pub struct Data {
+    a: GCounter<u64>,
+}
+
+fn main() {}
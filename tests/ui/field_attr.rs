@@ -0,0 +1,10 @@
+use crdts::GCounter;
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct Data {
+    #[crdt(ignore)]
+    a: GCounter<u64>,
+}
+
+fn main() {}
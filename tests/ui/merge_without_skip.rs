@@ -0,0 +1,12 @@
+use crdts::GCounter;
+use crdts_macro::crdt;
+
+fn merge_a(_: &mut GCounter<u64>, _: GCounter<u64>) {}
+
+#[crdt(u64)]
+pub struct Data {
+    #[crdt(merge = "merge_a")]
+    a: GCounter<u64>,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use crdts::GCounter;
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct DataV1 {
+    a: GCounter<u64>,
+}
+
+#[crdt(u64, version = 2, migrate_from = "DataV1")]
+pub struct Data {
+    a: GCounter<u64>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use crdts::GCounter;
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct Data {
+    none_op: GCounter<u64>,
+}
+
+fn main() {}
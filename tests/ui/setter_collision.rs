@@ -0,0 +1,10 @@
+use crdts::GCounter;
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct Data {
+    a: GCounter<u64>,
+    set_a_op: GCounter<u64>,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use crdts::GCounter;
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct Data(GCounter<u64>);
+
+fn main() {}
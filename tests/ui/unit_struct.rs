@@ -0,0 +1,6 @@
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct Data;
+
+fn main() {}
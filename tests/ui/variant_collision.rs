@@ -0,0 +1,11 @@
+use crdts::GCounter;
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+#[allow(non_snake_case)]
+pub struct Data {
+    foo_bar: GCounter<u64>,
+    fooBar: GCounter<u64>,
+}
+
+fn main() {}
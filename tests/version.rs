@@ -0,0 +1,88 @@
+use crdts::{CmRDT, GCounter, Orswot};
+use crdts_macro::crdt;
+
+#[crdt(u64)]
+pub struct DataV1 {
+    count: GCounter<u64>,
+}
+
+#[crdt(
+    u64,
+    version = 2,
+    migrate_from = "DataV1",
+    with = "upgrade",
+    op_with = "upgrade_op"
+)]
+pub struct Data {
+    visits: GCounter<u64>,
+    tags: Orswot<String, u64>,
+}
+
+fn upgrade(old: DataV1) -> Data {
+    Data {
+        visits: old.count,
+        tags: Orswot::new(),
+        v_clock: old.v_clock,
+        version: Default::default(),
+    }
+}
+
+fn upgrade_op(old: DataV1CrdtOp) -> DataCrdtOp {
+    DataCrdtOp {
+        dot: old.dot,
+        visits_op: old.count_op,
+        tags_op: None,
+        version: Default::default(),
+    }
+}
+
+fn old_state_and_op() -> (DataV1, DataV1CrdtOp) {
+    let mut old = DataV1::default();
+    let op = old.transact(1, |tx| {
+        tx.count(|c, actor| c.inc(actor));
+    });
+    old.apply(op);
+    let op = old.transact(1, |tx| {
+        tx.count(|c, actor| c.inc(actor));
+    });
+    (old, op)
+}
+
+#[test]
+fn versioned_states() {
+    let (old, _) = old_state_and_op();
+    let json = serde_json::to_value(&old).unwrap();
+    assert!(serde_json::from_value::<Data>(json.clone()).is_err());
+
+    let data = Data::deserialize_migrating(&json).unwrap();
+    assert_eq!(data.visits.read(), 1u8.into());
+    assert_eq!(data.v_clock, old.v_clock);
+
+    let json = serde_json::to_value(&data).unwrap();
+    assert_eq!(json["version"], 2);
+    let current = Data::deserialize_migrating(&json).unwrap();
+    assert_eq!(current, data);
+
+    let mut newer = json;
+    newer["version"] = 3.into();
+    assert!(Data::deserialize_migrating(&newer).is_err());
+}
+
+#[test]
+fn versioned_ops() {
+    let (old, old_op) = old_state_and_op();
+    let mut data = upgrade(old);
+    let json = serde_json::to_value(&old_op).unwrap();
+    assert!(serde_json::from_value::<DataCrdtOp>(json.clone()).is_err());
+
+    let op = DataCrdtOp::deserialize_migrating(&json).unwrap();
+    data.apply(op);
+    assert_eq!(data.visits.read(), 2u8.into());
+
+    let op = data.transact(2, |tx| {
+        tx.tags(|t, ctx| t.add("x".to_string(), ctx));
+    });
+    let json = serde_json::to_value(&op).unwrap();
+    assert_eq!(json["version"], 2);
+    assert_eq!(DataCrdtOp::deserialize_migrating(&json).unwrap(), op);
+}
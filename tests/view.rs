@@ -0,0 +1,62 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crdts::{CmRDT, GCounter, GSet, MVReg, Map, Orswot};
+use crdts_macro::{crdt, IntoView};
+
+#[crdt(u64, embedded, view)]
+pub struct Profile {
+    name: MVReg<String, u64>,
+    tags: GSet<String>,
+}
+
+#[crdt(u64, view)]
+pub struct Data {
+    a: Orswot<String, String>,
+    b: Map<u64, Orswot<Vec<u8>, u64>, u64>,
+    d: GCounter<u64>,
+    profile: Profile,
+}
+
+#[test]
+fn plain_values() {
+    let mut data = Data::default();
+    let op = data.transact(1, |tx| {
+        // `a` has `String` actors, its context is not the transaction's
+        let ctx = data.a.read_ctx().derive_add_ctx("x".to_string());
+        tx.set_a_op(data.a.add("x".to_string(), ctx));
+        tx.b(|b, ctx| b.update(7u64, ctx, |set, ctx| set.add(vec![1], ctx)));
+        tx.d(|d, actor| d.inc(actor));
+        tx.profile(|p, actor| ProfileCrdtOp {
+            name_op: Some(
+                p.name
+                    .write("ann".to_string(), p.name.read_ctx().derive_add_ctx(actor)),
+            ),
+            tags_op: Some("admin".to_string()),
+        });
+    });
+    data.apply(op);
+
+    let view = data.view();
+    assert_eq!(
+        view,
+        DataView {
+            a: HashSet::from(["x".to_string()]),
+            b: BTreeMap::from([(7, HashSet::from([vec![1]]))]),
+            d: 1,
+            profile: ProfileView {
+                name: vec!["ann".to_string()],
+                tags: BTreeSet::from(["admin".to_string()]),
+            },
+        }
+    );
+    assert_eq!(IntoView::view(&data), view);
+    assert_eq!(
+        serde_json::to_value(&view).unwrap(),
+        serde_json::json!({
+            "a": ["x"],
+            "b": {"7": [[1]]},
+            "d": 1,
+            "profile": {"name": ["ann"], "tags": ["admin"]},
+        })
+    );
+}